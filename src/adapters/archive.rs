@@ -1,4 +1,9 @@
 use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -29,15 +34,13 @@ async fn unarchive(package: Package, file: LocalVersion) -> Result<()> {
 }
 
 fn expand(package: Package, tmp: LocalVersion) -> Result<()> {
-    use std::fs::File;
     use std::os::unix::fs::PermissionsExt;
 
-    use anyhow::Context;
     use flate2::read::GzDecoder;
-    use tar::Archive;
 
-    if fs::metadata(&tmp.file_name).is_ok() {
-        fs::remove_dir_all(&tmp.file_name)?;
+    let output = Path::new(&tmp.path).join(&tmp.file_name);
+    if fs::metadata(&output).is_ok() {
+        fs::remove_dir_all(&output)?;
     }
 
     let file_path = format!("{}/{}.{}", tmp.path, tmp.file_name, tmp.file_format);
@@ -49,42 +52,396 @@ fn expand(package: Package, tmp: LocalVersion) -> Result<()> {
         )
     })?;
 
-    let output = format!("{}/{}", tmp.path, tmp.file_name);
-
     let context_msg = format!(
         "Failed to decompress or extract file {}.{}",
         tmp.file_name, tmp.file_format
     );
 
-    match tmp.file_format.as_str() {
-        "tar.gz" => {
-            let decompress_stream = GzDecoder::new(file);
-            let mut archive = Archive::new(decompress_stream);
-            archive.unpack(&output).with_context(|| context_msg)?;
+    let unpacked = match tmp.file_format.as_str() {
+        "tar.gz" => unpack_tar(GzDecoder::new(file), &output),
+        "tar.xz" => unpack_tar(XzDecoder::new(file), &output),
+        "zip" => unpack_zip(file, &output),
+        _ => return Err(anyhow!("Unsupported file format")),
+    };
+
+    if let Err(error) = unpacked {
+        let _ = fs::remove_dir_all(&output);
+        return Err(anyhow!("{context_msg}. additional info: {error}"));
+    }
+
+    for name in package.binary_names() {
+        let binary = output.join(package.binary_path()).join(name);
+        let mut perms = fs::metadata(&binary)?.permissions();
+        perms.set_mode(0o551);
+        fs::set_permissions(&binary, perms)?;
+    }
+
+    Ok(())
+}
+
+/// Unpacks a tar stream into `output`, refusing entries that would land
+/// outside of it.
+fn unpack_tar(reader: impl Read, output: &Path) -> Result<()> {
+    fs::create_dir_all(output)?;
+    let root = fs::canonicalize(output)?;
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.display().to_string();
+        let relative =
+            sanitize_entry_path(&entry.path()?).map_err(|reason| refused(&name, reason))?;
+        if relative.as_os_str().is_empty() {
+            continue;
         }
-        "tar.xz" => {
-            let decompress_stream = XzDecoder::new(file);
-            let mut archive = Archive::new(decompress_stream);
-            archive.unpack(&output).with_context(|| context_msg)?;
+
+        let kind = entry.header().entry_type();
+        if let Some(target) = entry.link_name()? {
+            let escapes = if kind.is_hard_link() {
+                sanitize_entry_path(&target).err()
+            } else {
+                check_link_target(&relative, &target).err()
+            };
+            if let Some(reason) = escapes {
+                return Err(refused(&name, reason));
+            }
         }
-        "zip" => {
-            let mut archive = ZipReader::new(file)
-                .map_err(|error| anyhow!("{context_msg}. additional info: {error}"))?;
-            archive.extract(&output).with_context(|| context_msg)?;
+
+        if !entry.unpack_in(&root).map_err(|error| refused(&name, &error.to_string()))? {
+            return Err(refused(&name, "path escapes the install directory"));
         }
-        _ => return Err(anyhow!("Unsupported file format")),
     }
 
-    let binary = &format!(
-        "{}/{}/{}",
-        tmp.file_name,
-        package.binary_path(),
-        package.binary_name()
-    );
+    verify_symlinks(&root)
+}
+
+/// Unpacks a zip archive into `output` with the same rules as
+/// [`unpack_tar`].
+fn unpack_zip(file: File, output: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::create_dir_all(output)?;
+    let root = fs::canonicalize(output)?;
+    let mut archive = ZipReader::new(file)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let name = entry.name().to_string();
+        let relative =
+            sanitize_entry_path(Path::new(&name)).map_err(|reason| refused(&name, reason))?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let dest = root.join(&relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&dest)?;
+            ensure_inside(&root, &dest).map_err(|reason| refused(&name, reason))?;
+            continue;
+        }
+
+        let parent = dest.parent().unwrap_or(&root);
+        fs::create_dir_all(parent)?;
+        ensure_inside(&root, parent).map_err(|reason| refused(&name, reason))?;
+        if fs::symlink_metadata(&dest).is_ok() {
+            fs::remove_file(&dest)?;
+        }
+
+        if entry.is_symlink() {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            check_link_target(&relative, Path::new(&target))
+                .map_err(|reason| refused(&name, reason))?;
+            std::os::unix::fs::symlink(&target, &dest)?;
+            continue;
+        }
 
-    let mut perms = fs::metadata(binary)?.permissions();
-    perms.set_mode(0o551);
-    fs::set_permissions(binary, perms)?;
+        let mut out = File::create(&dest)?;
+        std::io::copy(&mut entry, &mut out)?;
+        if let Some(mode) = entry.unix_mode() {
+            fs::set_permissions(&dest, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+
+    verify_symlinks(&root)
+}
+
+/// Returns the entry path relative to the install directory, rejecting
+/// absolute paths and `..` components. The archive root itself (`./`) maps to
+/// an empty path.
+fn sanitize_entry_path(path: &Path) -> std::result::Result<PathBuf, &'static str> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return Err("path contains a parent directory component"),
+            Component::RootDir | Component::Prefix(_) => return Err("path is absolute"),
+        }
+    }
+
+    Ok(relative)
+}
+
+/// Resolves a symlink target relative to the link's location and rejects it
+/// when it points outside of the install directory.
+fn check_link_target(link: &Path, target: &Path) -> std::result::Result<(), &'static str> {
+    let mut resolved: Vec<Component> =
+        link.parent().map(|p| p.components().collect()).unwrap_or_default();
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if resolved.pop().is_none() {
+                    return Err("link target points outside the install directory");
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err("link target is absolute"),
+        }
+    }
 
     Ok(())
 }
+
+fn ensure_inside(root: &Path, path: &Path) -> std::result::Result<(), &'static str> {
+    match fs::canonicalize(path) {
+        Ok(real) if real.starts_with(root) => Ok(()),
+        _ => Err("path resolves outside the install directory through a symlink"),
+    }
+}
+
+/// Walks the extracted tree and fails if any symlink resolves outside of
+/// `root`, which lexical checks alone cannot rule out for links that
+/// traverse other links.
+fn verify_symlinks(root: &Path) -> Result<()> {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let meta = fs::symlink_metadata(&path)?;
+            if meta.file_type().is_symlink() {
+                if !resolve_link(&path)?.starts_with(root) {
+                    let name = path.strip_prefix(root).unwrap_or(&path).display().to_string();
+                    return Err(refused(
+                        &name,
+                        "link target points outside the install directory",
+                    ));
+                }
+            } else if meta.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves a symlink component by component, following links that exist
+/// so that dangling targets are judged by where they would point.
+fn resolve_link(link: &Path) -> Result<PathBuf> {
+    let target = fs::read_link(link)?;
+    let mut resolved = fs::canonicalize(link.parent().unwrap_or(link))?;
+
+    for component in target.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(part) => {
+                resolved.push(part);
+                if let Ok(real) = fs::canonicalize(&resolved) {
+                    resolved = real;
+                }
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => resolved.push(component),
+        }
+    }
+
+    Ok(resolved)
+}
+
+fn refused(name: &str, reason: &str) -> anyhow::Error {
+    anyhow!("Refusing to extract archive entry '{name}': {reason}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hj-archive-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Builds a tar.gz with raw header names so that paths the `tar` builder
+    /// would reject can still be crafted.
+    fn crafted_tar(entries: &[(&str, tar::EntryType, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, kind, data) in entries {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(*kind);
+            header.set_mode(0o755);
+            if kind.is_symlink() || kind.is_hard_link() {
+                header.as_old_mut().linkname[..data.len()].copy_from_slice(data.as_bytes());
+                header.set_size(0);
+                header.set_cksum();
+                builder.append(&header, std::io::empty()).unwrap();
+            } else {
+                header.set_size(data.len() as u64);
+                header.set_cksum();
+                builder.append(&header, data.as_bytes()).unwrap();
+            }
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn unpack_crafted_tar(dir: &Path, entries: &[(&str, tar::EntryType, &str)]) -> Result<()> {
+        let bytes = crafted_tar(entries);
+        unpack_tar(
+            flate2::read::GzDecoder::new(bytes.as_slice()),
+            &dir.join("out"),
+        )
+    }
+
+    fn crafted_zip(dir: &Path, build: impl FnOnce(&mut ZipWriter<File>)) -> File {
+        let path = dir.join("archive.zip");
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        build(&mut writer);
+        writer.finish().unwrap();
+        File::open(path).unwrap()
+    }
+
+    #[test]
+    fn tar_extracts_regular_entries_and_inner_links() {
+        let dir = scratch_dir("tar-ok");
+        let entries = [
+            ("bin/tool", tar::EntryType::Regular, "#!/bin/sh\n"),
+            ("tool", tar::EntryType::Symlink, "bin/tool"),
+        ];
+
+        unpack_crafted_tar(&dir, &entries).unwrap();
+
+        assert!(dir.join("out/bin/tool").is_file());
+        assert!(dir.join("out/tool").is_file());
+    }
+
+    #[test]
+    fn tar_refuses_parent_dir_entries() {
+        let dir = scratch_dir("tar-parent");
+        let entries = [("../evil", tar::EntryType::Regular, "pwned")];
+
+        let error = unpack_crafted_tar(&dir, &entries).unwrap_err().to_string();
+
+        assert!(error.contains("'../evil'"), "{error}");
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[test]
+    fn tar_refuses_absolute_entries() {
+        let dir = scratch_dir("tar-absolute");
+        let target = format!("{}/evil", dir.display());
+        let entries = [(target.as_str(), tar::EntryType::Regular, "pwned")];
+
+        let error = unpack_crafted_tar(&dir, &entries).unwrap_err().to_string();
+
+        assert!(error.contains("path is absolute"), "{error}");
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[test]
+    fn tar_refuses_symlinks_escaping_the_root() {
+        let dir = scratch_dir("tar-symlink");
+        let entries = [
+            ("escape", tar::EntryType::Symlink, ".."),
+            ("escape/evil", tar::EntryType::Regular, "pwned"),
+        ];
+
+        let error = unpack_crafted_tar(&dir, &entries).unwrap_err().to_string();
+
+        assert!(error.contains("'escape'"), "{error}");
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[test]
+    fn tar_refuses_chained_symlinks_escaping_the_root() {
+        let dir = scratch_dir("tar-chain");
+        let entries = [
+            ("here", tar::EntryType::Symlink, "."),
+            ("sneaky", tar::EntryType::Symlink, "here/../evil"),
+        ];
+
+        let error = unpack_crafted_tar(&dir, &entries).unwrap_err().to_string();
+
+        assert!(error.contains("sneaky"), "{error}");
+    }
+
+    #[test]
+    fn tar_refuses_hard_links_outside_the_root() {
+        let dir = scratch_dir("tar-hardlink");
+        let entries = [("passwd", tar::EntryType::Link, "/etc/passwd")];
+
+        let error = unpack_crafted_tar(&dir, &entries).unwrap_err().to_string();
+
+        assert!(error.contains("'passwd'"), "{error}");
+        assert!(!dir.join("out/passwd").exists());
+    }
+
+    #[test]
+    fn zip_extracts_regular_entries() {
+        let dir = scratch_dir("zip-ok");
+        let file = crafted_zip(&dir, |zip| {
+            zip.start_file(
+                "bin/tool",
+                SimpleFileOptions::default().unix_permissions(0o755),
+            )
+            .unwrap();
+            zip.write_all(b"#!/bin/sh\n").unwrap();
+            zip.add_symlink("tool", "bin/tool", SimpleFileOptions::default()).unwrap();
+        });
+
+        unpack_zip(file, &dir.join("out")).unwrap();
+
+        assert!(dir.join("out/bin/tool").is_file());
+        assert!(dir.join("out/tool").is_file());
+    }
+
+    #[test]
+    fn zip_refuses_parent_dir_entries() {
+        let dir = scratch_dir("zip-parent");
+        let file = crafted_zip(&dir, |zip| {
+            zip.start_file("../evil", SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"pwned").unwrap();
+        });
+
+        let error = unpack_zip(file, &dir.join("out")).unwrap_err().to_string();
+
+        assert!(error.contains("'../evil'"), "{error}");
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[test]
+    fn zip_refuses_symlinks_escaping_the_root() {
+        let dir = scratch_dir("zip-symlink");
+        let file = crafted_zip(&dir, |zip| {
+            zip.add_symlink("escape", "../../etc", SimpleFileOptions::default()).unwrap();
+        });
+
+        let error = unpack_zip(file, &dir.join("out")).unwrap_err().to_string();
+
+        assert!(error.contains("'escape'"), "{error}");
+        assert!(fs::symlink_metadata(dir.join("out/escape")).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use sha2::Digest;
+use tracing::warn;
+
+use crate::adapters::digest::hash_file;
+use crate::adapters::digest::hex;
+use crate::adapters::digest::sha256_hex;
+use crate::domain::cache::CacheEntry;
+use crate::domain::cache::CacheUsage;
+use crate::ports::DownloadCache;
+
+/// Content-addressed download cache.
+///
+/// Archives live in `blobs/<sha256>` and `index/<sha256 of url>` records which
+/// blob a download URL resolved to, so identical archives are stored once. A
+/// blob is removed once no URL resolves to it anymore, like the older build of
+/// a moving tag.
+#[derive(Clone)]
+pub struct FsDownloadCache {
+    dir: PathBuf,
+}
+
+impl FsDownloadCache {
+    pub fn new(dir: PathBuf) -> Self { Self { dir } }
+
+    fn blob(&self, digest: &str) -> PathBuf { self.dir.join("blobs").join(digest) }
+
+    fn index(&self, url: &str) -> PathBuf { self.dir.join("index").join(sha256_hex(url)) }
+
+    /// The blob `url` resolved to, without checking its contents.
+    async fn entry(&self, url: &str, digest: String) -> anyhow::Result<Option<CacheEntry>> {
+        let path = self.blob(&digest);
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) => Ok(Some(CacheEntry {
+                url: url.to_string(),
+                digest,
+                size: metadata.len(),
+                path,
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Every `(digest, url)` pair in the index.
+    async fn index_entries(&self) -> anyhow::Result<Vec<(String, String)>> {
+        let mut dir = match tokio::fs::read_dir(self.dir.join("index")).await {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entries = Vec::new();
+        while let Some(entry) = dir.next_entry().await? {
+            if let Some(entry) = read_index(&entry.path()).await? {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+impl DownloadCache for FsDownloadCache {
+    async fn get(&self, url: &str, expected: Option<&str>) -> anyhow::Result<Option<CacheEntry>> {
+        let Some((digest, _)) = read_index(&self.index(url)).await? else {
+            return Ok(None);
+        };
+
+        if expected.is_some_and(|expected| !expected.eq_ignore_ascii_case(&digest)) {
+            return Ok(None);
+        }
+
+        let Some(entry) = self.entry(url, digest).await? else {
+            return Ok(None);
+        };
+
+        // The blob may have been damaged since it was cached.
+        let actual = hex(&hash_file(&entry.path).await?.finalize());
+        if actual != entry.digest {
+            warn!(
+                "Cached archive {} does not match its sha256, discarding it",
+                entry.path.display()
+            );
+            remove_if_exists(&entry.path).await?;
+            return Ok(None);
+        }
+
+        Ok(Some(entry))
+    }
+
+    async fn put(&self, url: &str, file: &Path, digest: &str) -> anyhow::Result<()> {
+        let blob = self.blob(digest);
+        let index = self.index(url);
+        tokio::fs::create_dir_all(blob.parent().unwrap_or(&self.dir)).await?;
+        tokio::fs::create_dir_all(index.parent().unwrap_or(&self.dir)).await?;
+
+        if tokio::fs::metadata(&blob).await.is_err() {
+            let staged = blob.with_extension("tmp");
+            tokio::fs::copy(file, &staged).await?;
+            tokio::fs::rename(&staged, &blob).await?;
+        }
+
+        let replaced = read_index(&index).await?.map(|(digest, _)| digest);
+        tokio::fs::write(&index, format!("{digest} {url}\n")).await?;
+
+        let Some(replaced) = replaced.filter(|replaced| replaced != digest) else {
+            return Ok(());
+        };
+        let referenced = self.index_entries().await?.iter().any(|(digest, _)| *digest == replaced);
+        if !referenced {
+            remove_if_exists(&self.blob(&replaced)).await?;
+        }
+        Ok(())
+    }
+
+    async fn entries(&self) -> anyhow::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for (digest, url) in self.index_entries().await? {
+            if let Some(entry) = self.entry(&url, digest).await? {
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by(|a, b| a.url.cmp(&b.url));
+        Ok(entries)
+    }
+
+    async fn usage(&self) -> anyhow::Result<CacheUsage> {
+        let mut usage = CacheUsage {
+            archives: 0,
+            size: 0,
+        };
+        let mut dir = match tokio::fs::read_dir(self.dir.join("blobs")).await {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(usage),
+            Err(err) => return Err(err.into()),
+        };
+
+        while let Some(entry) = dir.next_entry().await? {
+            // Skips archives still being staged by `put`.
+            let metadata = entry.metadata().await?;
+            if metadata.is_file() && entry.path().extension().is_none() {
+                usage.archives += 1;
+                usage.size += metadata.len();
+            }
+        }
+        Ok(usage)
+    }
+
+    async fn clean(&self) -> anyhow::Result<()> {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn read_index(path: &Path) -> anyhow::Result<Option<(String, String)>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(contents
+        .trim()
+        .split_once(' ')
+        .map(|(digest, url)| (digest.to_string(), url.to_string())))
+}
+
+async fn remove_if_exists(path: &Path) -> anyhow::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::Sha256;
+
+    use super::*;
+
+    const URL: &str = "https://example.com/tool-v1.0.0.tar.gz";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hj-cache-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes `contents` to a file in `dir` and returns it with its digest.
+    fn archive(dir: &Path, name: &str, contents: &str) -> (PathBuf, String) {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        (path, hex(&Sha256::digest(contents.as_bytes())))
+    }
+
+    #[tokio::test]
+    async fn put_then_get_returns_the_blob() {
+        let dir = scratch_dir("get");
+        let cache = FsDownloadCache::new(dir.join("cache"));
+        let (file, digest) = archive(&dir, "a.tar.gz", "archive");
+
+        cache.put(URL, &file, &digest).await.unwrap();
+        let entry = cache.get(URL, None).await.unwrap().unwrap();
+        assert_eq!(entry.digest, digest);
+        assert_eq!(entry.size, 7);
+        assert_eq!(std::fs::read_to_string(&entry.path).unwrap(), "archive");
+
+        assert!(cache.get(URL, Some(&digest.to_uppercase())).await.unwrap().is_some());
+        assert!(cache.get(URL, Some(&"0".repeat(64))).await.unwrap().is_none());
+        assert!(cache.get("https://example.com/other", None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn damaged_blobs_are_discarded() {
+        let dir = scratch_dir("damaged");
+        let cache = FsDownloadCache::new(dir.join("cache"));
+        let (file, digest) = archive(&dir, "a.tar.gz", "archive");
+        cache.put(URL, &file, &digest).await.unwrap();
+
+        let blob = cache.blob(&digest);
+        std::fs::write(&blob, "tampered").unwrap();
+        assert!(cache.get(URL, None).await.unwrap().is_none());
+        assert!(cache.get(URL, Some(&digest)).await.unwrap().is_none());
+        assert!(!blob.exists());
+    }
+
+    #[tokio::test]
+    async fn shared_blobs_are_stored_and_counted_once() {
+        let dir = scratch_dir("shared");
+        let cache = FsDownloadCache::new(dir.join("cache"));
+        let (file, digest) = archive(&dir, "a.tar.gz", "archive");
+        let mirror = "https://mirror.example.com/tool-v1.0.0.tar.gz";
+        cache.put(URL, &file, &digest).await.unwrap();
+        cache.put(mirror, &file, &digest).await.unwrap();
+
+        let entries = cache.entries().await.unwrap();
+        let urls: Vec<&str> = entries.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(urls, [URL, mirror]);
+
+        let usage = cache.usage().await.unwrap();
+        assert_eq!((usage.archives, usage.size), (1, 7));
+    }
+
+    #[tokio::test]
+    async fn replaced_blobs_are_removed_once_unreferenced() {
+        let dir = scratch_dir("replaced");
+        let cache = FsDownloadCache::new(dir.join("cache"));
+        let mirror = "https://mirror.example.com/tool-v1.0.0.tar.gz";
+        let (old, old_digest) = archive(&dir, "old.tar.gz", "old build");
+        let (new, new_digest) = archive(&dir, "new.tar.gz", "new build");
+
+        cache.put(URL, &old, &old_digest).await.unwrap();
+        cache.put(mirror, &old, &old_digest).await.unwrap();
+        cache.put(URL, &new, &new_digest).await.unwrap();
+        assert!(cache.blob(&old_digest).exists());
+
+        cache.put(mirror, &new, &new_digest).await.unwrap();
+        assert!(!cache.blob(&old_digest).exists());
+        assert_eq!(cache.usage().await.unwrap().archives, 1);
+    }
+
+    #[tokio::test]
+    async fn clean_removes_everything() {
+        let dir = scratch_dir("clean");
+        let cache = FsDownloadCache::new(dir.join("cache"));
+        let (file, digest) = archive(&dir, "a.tar.gz", "archive");
+        cache.put(URL, &file, &digest).await.unwrap();
+
+        cache.clean().await.unwrap();
+        assert!(cache.entries().await.unwrap().is_empty());
+        assert_eq!(cache.usage().await.unwrap().archives, 0);
+        // Cleaning an empty cache is fine too.
+        cache.clean().await.unwrap();
+    }
+}
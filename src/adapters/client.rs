@@ -1,4 +1,5 @@
 use std::env::var;
+use std::time::Duration;
 
 use anyhow::Error;
 use reqwest::header::HeaderMap;
@@ -9,7 +10,9 @@ use reqwest::Client;
 /// Creates a new `reqwest::Client` with default headers.
 ///
 /// This function fetches the `GITHUB_TOKEN` environment variable and uses it to
-/// set the `Authorization` header for the client.
+/// set the `Authorization` header for the client. `connect_timeout` bounds
+/// establishing a connection and `read_timeout` each read from it, so a stalled
+/// download fails and can be retried instead of hanging.
 ///
 /// # Returns
 ///
@@ -20,19 +23,31 @@ use reqwest::Client;
 /// # Example
 ///
 /// ```rust
-/// let client = create_reqwest_client(); 
+/// let client = create_reqwest_client(Duration::from_secs(30), Duration::from_secs(60)); 
 /// ```
 ///
 /// # Errors
 ///
 /// This function will return an error if the `reqwest::Client` could not be
 /// built.
-pub fn create_reqwest_client() -> Result<Client, Error> {
+pub fn create_reqwest_client(
+    connect_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<Client, Error> {
     let mut headers = HeaderMap::new();
     if let Ok(token) = var("GITHUB_TOKEN") {
         let token = HeaderValue::from_str(&format!("token {}", token))?;
         headers.insert(AUTHORIZATION, token);
     }
 
-    Ok(Client::builder().default_headers(headers).build()?)
+    Ok(Client::builder()
+        .default_headers(headers)
+        .connect_timeout(connect_timeout)
+        .read_timeout(read_timeout)
+        .build()?)
+}
+
+/// Returns the HTTP client, or an error when running with `--offline`.
+pub fn require(client: Option<&Client>) -> Result<&Client, Error> {
+    client.ok_or_else(|| anyhow::anyhow!("Network access is disabled by --offline"))
 }
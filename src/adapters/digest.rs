@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+
+/// Lowercase hex encoding of `bytes`.
+pub fn hex(bytes: &[u8]) -> String { bytes.iter().map(|byte| format!("{byte:02x}")).collect() }
+
+/// Hex SHA-256 of `value`, used to name files after URLs.
+pub fn sha256_hex(value: &str) -> String { hex(&Sha256::digest(value.as_bytes())) }
+
+/// Feeds the contents of the file at `path` to a new hasher.
+pub async fn hash_file(path: &Path) -> anyhow::Result<Sha256> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher)
+}
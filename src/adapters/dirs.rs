@@ -27,16 +27,36 @@ pub fn ensure_root_dir(explicit: Option<&Path>, env: &dyn Env) -> miette::Result
     Ok(defined)
 }
 
+/// Downloads are cached outside the root dir so that several roots share them.
+fn cache_dir(env: &dyn Env, root_dir: &Path) -> PathBuf {
+    if let Some(dir) = env.cache_dir() {
+        return dir;
+    }
+
+    match env.home_dir() {
+        Some(home) => home.join(".cache").join(DEFAULT_PATH_NAME),
+        None => root_dir.join("cache"),
+    }
+}
+
 pub struct Dirs {
     pub root_dir: PathBuf,
+    pub cache_dir: PathBuf,
 }
 
 impl Dirs {
     pub fn try_new(root_dir: Option<&Path>, env: &dyn Env) -> miette::Result<Self> {
         let root_dir = ensure_root_dir(root_dir, env)?;
+        let cache_dir = cache_dir(env, &root_dir);
 
-        Ok(Self { root_dir })
+        Ok(Self {
+            root_dir,
+            cache_dir,
+        })
     }
+
+    /// Where fetched `[[source]]` registries are kept.
+    pub fn registry_dir(&self) -> PathBuf { self.root_dir.join("registry") }
 }
 
 impl RootDir for Dirs {
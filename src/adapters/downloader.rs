@@ -1,15 +1,36 @@
+use std::future::Future;
 use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use anyhow::anyhow;
 use anyhow::Result;
 use futures_util::StreamExt;
-use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
+use reqwest::header::CONTENT_RANGE;
+use reqwest::header::ETAG;
+use reqwest::header::IF_RANGE;
+use reqwest::header::LAST_MODIFIED;
+use reqwest::header::RANGE;
 use reqwest::Client;
+use reqwest::StatusCode;
+use sha2::Digest;
+use sha2::Sha256;
 use tokio::io::AsyncWriteExt;
+use tracing::info;
+use tracing::info_span;
+use tracing::warn;
+use tracing::Instrument;
+use tracing::Span;
+use tracing_indicatif::span_ext::IndicatifSpanExt;
 
+use crate::adapters::client::require;
+use crate::adapters::digest::hash_file;
+use crate::adapters::digest::hex;
 use crate::ports::Downloader;
 
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
 pub struct ReqwestDownloader {
     client: Option<reqwest::Client>,
 }
@@ -20,36 +41,464 @@ impl ReqwestDownloader {
             client: client.cloned(),
         }
     }
+
+    fn client(&self) -> Result<&Client> { require(self.client.as_ref()) }
 }
 
 impl Downloader for ReqwestDownloader {
-    async fn download(&self, url: &str, dest: &Path) -> Result<()> {
-        let client: &Client = self.client.as_ref().expect("Client not found");
-        let response = client.get(url).send().await?.error_for_status()?;
-        let total_size = response
-            .content_length()
-            .ok_or_else(|| anyhow!("Failed to get content length"))?;
-
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{bar:40.cyan/blue} {bytes}/{total_bytes} {msg}")
-                .unwrap(),
-        );
+    /// Downloads into `<dest>.part`, resuming a previous partial download with
+    /// a `Range` request, and moves it to `dest` once complete. The archive is
+    /// hashed as it streams in.
+    async fn download(&self, url: &str, dest: &Path) -> Result<String> {
+        let part = part_path(dest);
+        let client = self.client()?;
+        let digest = with_retries(url, || download_part(client, url, &part)).await?;
+        tokio::fs::rename(&part, dest).await?;
+        remove_if_exists(&validator_path(&part)).await?;
+        Ok(digest)
+    }
 
-        let mut file = tokio::fs::File::create(dest).await?;
-        let mut downloaded: u64 = 0;
-        let mut stream = response.bytes_stream();
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let client = self.client()?;
+        with_retries(url, || async {
+            let response = client.get(url).send().await?.error_for_status()?;
+            Ok(response.text().await?)
+        })
+        .await
+    }
+
+    async fn exists(&self, url: &str) -> Result<bool> {
+        let client = self.client()?;
+        with_retries(url, || async {
+            let response = client.head(url).send().await?;
+            match response.status() {
+                StatusCode::NOT_FOUND => Ok(false),
+                _ => Ok(response.error_for_status().map(|_| true)?),
+            }
+        })
+        .await
+    }
+}
+
+/// Marks a download that ended before all of its bytes arrived, which is
+/// worth resuming.
+#[derive(Debug)]
+struct Incomplete {
+    received: u64,
+    expected: u64,
+}
+
+impl std::fmt::Display for Incomplete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "connection closed after {} of {} bytes",
+            self.received, self.expected
+        )
+    }
+}
+
+impl std::error::Error for Incomplete {}
+
+/// Retries `attempt` with exponential backoff while it fails with a
+/// transient error.
+async fn with_retries<T, F, Fut>(url: &str, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut tries = 1;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(err) if tries < MAX_ATTEMPTS && is_transient(&err) => {
+                let delay = INITIAL_BACKOFF * 2u32.pow(tries - 1);
+                warn!("Request to {url} failed ({err}), retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                tries += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    if err.is::<Incomplete>() {
+        return true;
+    }
+
+    let Some(err) = err.downcast_ref::<reqwest::Error>() else {
+        return false;
+    };
+
+    match err.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => {
+            err.is_timeout()
+                || err.is_connect()
+                || err.is_request()
+                || err.is_body()
+                || err.is_decode()
+        }
+    }
+}
+
+/// Downloads `url` into `part`, resuming what is already there when the
+/// remote file is still the one it came from, and returns the hex SHA-256 of
+/// the whole file.
+async fn download_part(client: &Client, url: &str, part: &Path) -> Result<String> {
+    let offset = match tokio::fs::metadata(part).await {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err.into()),
+    };
+    let validator_file = validator_path(part);
+    let validator = match offset {
+        0 => None,
+        _ => read_optional(&validator_file).await?,
+    };
 
+    // Without a validator there is no telling whether the remote file changed
+    // since the partial one was written, so only resume with `If-Range`.
+    let mut request = client.get(url);
+    if let Some(validator) = validator.as_deref() {
+        request = request.header(RANGE, format!("bytes={offset}-")).header(IF_RANGE, validator);
+    }
+    let response = request.send().await?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        if complete_length(&response) == Some(offset) {
+            return Ok(hex(&hash_file(part).await?.finalize()));
+        }
+        // The partial file does not match the remote one, start over.
+        tokio::fs::remove_file(part).await?;
+        return Err(Incomplete {
+            received: 0,
+            expected: complete_length(&response).unwrap_or_default(),
+        }
+        .into());
+    }
+
+    let response = response.error_for_status()?;
+    let resumed = validator.is_some() && response.status() == StatusCode::PARTIAL_CONTENT;
+    let start = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|length| start + length);
+
+    let mut hasher = match resumed {
+        true => hash_file(part).await?,
+        false => {
+            match response_validator(&response) {
+                Some(validator) => tokio::fs::write(&validator_file, validator).await?,
+                None => remove_if_exists(&validator_file).await?,
+            }
+            Sha256::new()
+        }
+    };
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(part)
+        .await?;
+
+    // The bar belongs to a span so concurrent downloads share the progress
+    // display of the tracing layer instead of drawing over each other.
+    let span = progress_span(url, total);
+    span.pb_set_position(start);
+    if resumed {
+        span.pb_set_message(&format!("resumed at {start} bytes"));
+    }
+
+    let downloaded = async {
+        let mut downloaded = start;
+        let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
             file.write_all(&chunk).await?;
+            hasher.update(&chunk);
             downloaded += chunk.len() as u64;
-            pb.set_position(downloaded);
+            Span::current().pb_set_position(downloaded);
+        }
+        file.flush().await?;
+        anyhow::Ok(downloaded)
+    }
+    .instrument(span)
+    .await?;
+
+    if let Some(expected) = total.filter(|&total| downloaded < total) {
+        return Err(Incomplete {
+            received: downloaded,
+            expected,
+        }
+        .into());
+    }
+
+    info!("Downloaded {url}");
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The strong `ETag`, or else the `Last-Modified` date, that a later `If-Range`
+/// can check the remote file against. Weak `ETag`s are not allowed there.
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name| response.headers().get(name)?.to_str().ok().map(str::to_string);
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+}
+
+/// A span showing the download as a bar when the size is known, and as a
+/// spinner for chunked responses.
+fn progress_span(url: &str, total: Option<u64>) -> Span {
+    let span = info_span!("download", url);
+    match total {
+        Some(total) => {
+            span.pb_set_style(
+                &ProgressStyle::default_bar()
+                    .template("{bar:40.cyan/blue} {bytes}/{total_bytes} {msg}")
+                    .unwrap(),
+            );
+            span.pb_set_length(total);
+        }
+        None => span.pb_set_style(
+            &ProgressStyle::default_spinner().template("{spinner} {bytes} {msg}").unwrap(),
+        ),
+    }
+    span
+}
+
+/// Reads the complete length from a `Content-Range: bytes */<length>` header.
+fn complete_length(response: &reqwest::Response) -> Option<u64> {
+    let header = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    header.rsplit('/').next()?.parse().ok()
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Where the validator of the response a `.part` file came from is kept.
+fn validator_path(part: &Path) -> PathBuf {
+    let mut name = part.file_name().unwrap_or_default().to_os_string();
+    name.push(".validator");
+    part.with_file_name(name)
+}
+
+async fn read_optional(path: &Path) -> Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hj-download-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn client() -> Client { Client::builder().no_proxy().build().unwrap() }
+
+    fn raw_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {status}\r\nConnection: close\r\n");
+        for (name, value) in headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
         }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+        [head.as_bytes(), body].concat()
+    }
+
+    /// Answers one connection per response, in order, and hands back the
+    /// requests it received.
+    async fn serve(responses: Vec<Vec<u8>>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/tool.tar.gz", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+                requests.push(String::from_utf8_lossy(&request).to_lowercase());
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    async fn fetch_response(response: Vec<u8>) -> reqwest::Response {
+        let (url, _server) = serve(vec![response]).await;
+        client().get(&url).send().await.unwrap()
+    }
+
+    fn sha256(bytes: &[u8]) -> String { hex(&Sha256::digest(bytes)) }
+
+    #[test]
+    fn part_files_sit_next_to_the_destination() {
+        let part = part_path(Path::new("/tmp/tool.tar.gz"));
+        assert_eq!(part, Path::new("/tmp/tool.tar.gz.part"));
+        assert_eq!(
+            validator_path(&part),
+            Path::new("/tmp/tool.tar.gz.part.validator")
+        );
+    }
+
+    #[tokio::test]
+    async fn complete_length_reads_the_content_range() {
+        let response = fetch_response(raw_response(
+            "416 Range Not Satisfiable",
+            &[("Content-Range", "bytes */42")],
+            b"",
+        ))
+        .await;
+        assert_eq!(complete_length(&response), Some(42));
+
+        let response = fetch_response(raw_response("200 OK", &[], BODY)).await;
+        assert_eq!(complete_length(&response), None);
+    }
+
+    #[tokio::test]
+    async fn response_validator_prefers_strong_etags() {
+        let modified = "Wed, 01 Jan 2025 00:00:00 GMT";
+
+        let strong = [("ETag", "\"abc\""), ("Last-Modified", modified)];
+        let response = fetch_response(raw_response("200 OK", &strong, BODY)).await;
+        assert_eq!(response_validator(&response).as_deref(), Some("\"abc\""));
+
+        let weak = [("ETag", "W/\"abc\""), ("Last-Modified", modified)];
+        let response = fetch_response(raw_response("200 OK", &weak, BODY)).await;
+        assert_eq!(response_validator(&response).as_deref(), Some(modified));
+
+        let response = fetch_response(raw_response("200 OK", &[], BODY)).await;
+        assert_eq!(response_validator(&response), None);
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_cut_downloads_are_transient() {
+        let incomplete = anyhow::Error::new(Incomplete {
+            received: 1,
+            expected: 2,
+        });
+        assert!(is_transient(&incomplete));
+        assert!(!is_transient(&anyhow::anyhow!("checksum mismatch")));
+
+        for (status, transient) in [
+            ("503 Service Unavailable", true),
+            ("429 Too Many Requests", true),
+            ("404 Not Found", false),
+        ] {
+            let response = fetch_response(raw_response(status, &[], b"")).await;
+            let err = anyhow::Error::new(response.error_for_status().unwrap_err());
+            assert_eq!(is_transient(&err), transient, "{status}");
+        }
+
+        // Nothing listens on a port the listener was just dropped from.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let err = anyhow::Error::new(client().get(&url).send().await.unwrap_err());
+        assert!(is_transient(&err));
+    }
+
+    #[tokio::test]
+    async fn downloads_resume_from_the_partial_file() {
+        let dir = scratch_dir("resume");
+        let dest = dir.join("tool.tar.gz");
+        let part = part_path(&dest);
+        std::fs::write(&part, &BODY[..10]).unwrap();
+        std::fs::write(validator_path(&part), "\"abc\"").unwrap();
+
+        let range = format!("bytes 10-{}/{}", BODY.len() - 1, BODY.len());
+        let (url, server) = serve(vec![raw_response(
+            "206 Partial Content",
+            &[("Content-Range", &range), ("ETag", "\"abc\"")],
+            &BODY[10..],
+        )])
+        .await;
+
+        let client = client();
+        let digest = ReqwestDownloader::new(Some(&client)).download(&url, &dest).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("range: bytes=10-"));
+        assert!(requests[0].contains("if-range: \"abc\""));
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert_eq!(digest, sha256(BODY));
+        assert!(!part.exists());
+        assert!(!validator_path(&part).exists());
+    }
+
+    #[tokio::test]
+    async fn downloads_start_over_when_the_remote_file_changed() {
+        let dir = scratch_dir("changed");
+        let dest = dir.join("tool.tar.gz");
+        let part = part_path(&dest);
+        std::fs::write(&part, b"stale bytes").unwrap();
+        std::fs::write(validator_path(&part), "\"old\"").unwrap();
+
+        // A failed `If-Range` makes the server send the whole new file.
+        let (url, server) = serve(vec![raw_response("200 OK", &[("ETag", "\"new\"")], BODY)]).await;
+
+        let client = client();
+        let digest = ReqwestDownloader::new(Some(&client)).download(&url, &dest).await.unwrap();
+
+        server.await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert_eq!(digest, sha256(BODY));
+    }
+
+    #[tokio::test]
+    async fn complete_partial_files_are_not_downloaded_again() {
+        let dir = scratch_dir("complete");
+        let dest = dir.join("tool.tar.gz");
+        let part = part_path(&dest);
+        std::fs::write(&part, BODY).unwrap();
+        std::fs::write(validator_path(&part), "\"abc\"").unwrap();
+
+        let length = format!("bytes */{}", BODY.len());
+        let (url, server) = serve(vec![raw_response(
+            "416 Range Not Satisfiable",
+            &[("Content-Range", &length)],
+            b"",
+        )])
+        .await;
 
-        pb.finish_with_message(format!("Downloaded to {}", dest.display()));
+        let client = client();
+        let digest = ReqwestDownloader::new(Some(&client)).download(&url, &dest).await.unwrap();
 
-        Ok(())
+        server.await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert_eq!(digest, sha256(BODY));
     }
 }
@@ -1,6 +1,9 @@
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
+use crate::ports::FileStamp;
 use crate::ports::Fs;
 
 #[derive(Clone, Copy)]
@@ -36,11 +39,6 @@ impl Fs for TokioFs {
         Ok(())
     }
 
-    async fn set_current_dir(&self, path: &Path) -> anyhow::Result<()> {
-        std::env::set_current_dir(path)?;
-        Ok(())
-    }
-
     async fn is_dir(&self, path: &Path) -> anyhow::Result<bool> {
         Ok(tokio::fs::metadata(path).await?.is_dir())
     }
@@ -52,4 +50,73 @@ impl Fs for TokioFs {
             Err(err) => Err(err.into()),
         }
     }
+
+    async fn symlink(&self, target: &Path, link: &Path) -> anyhow::Result<()> {
+        tokio::fs::symlink(target, link).await?;
+        Ok(())
+    }
+
+    async fn hard_link(&self, src: &Path, dest: &Path) -> anyhow::Result<()> {
+        tokio::fs::hard_link(src, dest).await?;
+        Ok(())
+    }
+
+    async fn read_link(&self, path: &Path) -> anyhow::Result<Option<PathBuf>> {
+        match tokio::fs::read_link(path).await {
+            Ok(target) => Ok(Some(target)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) if err.kind() == std::io::ErrorKind::InvalidInput => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn rename(&self, src: &Path, dest: &Path) -> anyhow::Result<()> {
+        tokio::fs::rename(src, dest).await?;
+        Ok(())
+    }
+
+    async fn modified(&self, path: &Path) -> anyhow::Result<SystemTime> {
+        Ok(tokio::fs::metadata(path).await?.modified()?)
+    }
+
+    async fn read_to_string(&self, path: &Path) -> anyhow::Result<String> {
+        Ok(tokio::fs::read_to_string(path).await?)
+    }
+
+    async fn write(&self, path: &Path, contents: &str) -> anyhow::Result<()> {
+        tokio::fs::write(path, contents).await?;
+        Ok(())
+    }
+
+    async fn dir_size(&self, path: &Path) -> anyhow::Result<u64> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || dir_size(&path)).await?
+    }
+
+    async fn stamp(&self, path: &Path) -> anyhow::Result<Option<FileStamp>> {
+        let metadata = match tokio::fs::symlink_metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(FileStamp {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        }))
+    }
+}
+
+fn dir_size(path: &Path) -> anyhow::Result<u64> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        size += dir_size(&entry?.path())?;
+    }
+    Ok(size)
 }
@@ -5,6 +5,8 @@ use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::adapters::client::require;
+
 /// Represents an error response from the GitHub API.
 ///
 /// This struct contains information about an error response from the GitHub
@@ -37,18 +39,41 @@ pub struct ErrorResponse {
 }
 
 pub async fn api(client: Option<&Client>, url: String) -> Result<String> {
-    let response = client
-        .expect("Client not found")
+    let (response, _) = api_page(client, url).await?;
+    Ok(response)
+}
+
+/// Fetches one page of a paginated GitHub API listing.
+///
+/// Returns the response body together with the URL of the next page, taken
+/// from the `rel="next"` entry of the `Link` header.
+pub async fn api_page(client: Option<&Client>, url: String) -> Result<(String, Option<String>)> {
+    let response = require(client)?
         .get(url)
         .header(reqwest::header::USER_AGENT, "hyper-jump")
         .header(reqwest::header::ACCEPT, "application/vnd.github.v3+json")
         .send()
         .await?
-        .error_for_status()?
-        .text()
-        .await?;
+        .error_for_status()?;
 
-    Ok(response)
+    let next = response
+        .headers()
+        .get(reqwest::header::LINK)
+        .and_then(|value| value.to_str().ok())
+        .and_then(next_link);
+    let body = response.text().await?;
+
+    Ok((body, next))
+}
+
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
 }
 
 /// Deserializes a JSON response from the GitHub API.
@@ -92,3 +117,40 @@ pub fn deserialize_response<T: DeserializeOwned>(response: String) -> Result<T>
 
     Ok(serde_json::from_value(value)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_link_follows_the_next_relation() {
+        let header = "<https://api.github.com/repositories/1/releases?per_page=100&page=2>; \
+                      rel=\"next\", \
+                      <https://api.github.com/repositories/1/releases?per_page=100&page=5>; \
+                      rel=\"last\"";
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+        );
+    }
+
+    #[test]
+    fn next_link_finds_next_in_any_position() {
+        let header = "<https://example.com/releases?page=1>; rel=\"prev\", \
+                      <https://example.com/releases?page=1>; rel=\"first\", \
+                      <https://example.com/releases?page=3>; rel=\"next\"";
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("https://example.com/releases?page=3")
+        );
+    }
+
+    #[test]
+    fn next_link_is_missing_on_the_last_page() {
+        let header = "<https://example.com/releases?page=1>; rel=\"prev\", \
+                      <https://example.com/releases?page=1>; rel=\"first\"";
+        assert_eq!(next_link(header), None);
+        assert_eq!(next_link(""), None);
+        assert_eq!(next_link("<https://example.com/releases?page=2>"), None);
+    }
+}
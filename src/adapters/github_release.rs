@@ -1,7 +1,9 @@
+use anyhow::anyhow;
 use anyhow::Result;
 use serde::Deserialize;
 
 use crate::adapters::github::api;
+use crate::adapters::github::api_page;
 use crate::adapters::github::deserialize_response;
 use crate::domain::package::PackageSpec;
 use crate::domain::version::parse_normal_version;
@@ -28,6 +30,14 @@ impl GitHubReleaseProvider {
 
 impl ReleaseProvider for GitHubReleaseProvider {
     async fn latest(&self, package: &PackageSpec) -> Result<ParsedVersion> {
+        // GitHub's latest release may belong to another package of the repo.
+        if package.tag_pattern.is_some() {
+            let releases = self.list(package).await?;
+            return package
+                .newest_release(releases, None)
+                .ok_or_else(|| anyhow!("No release of {} matches its tag_pattern", package.id));
+        }
+
         let url = package.latest_url();
         let response = api(self.client.as_ref(), url).await?;
         let latest: UpstreamVersion = deserialize_response(response)?;
@@ -35,9 +45,22 @@ impl ReleaseProvider for GitHubReleaseProvider {
     }
 
     async fn list(&self, package: &PackageSpec) -> Result<Vec<RemoteVersion>> {
-        let url = package.releases_url();
-        let response = api(self.client.as_ref(), url).await?;
-        let versions: Vec<RemoteVersion> = deserialize_response(response)?;
+        let mut next = Some(format!("{}?per_page=100", package.releases_url()));
+        let mut versions = Vec::new();
+
+        while let Some(url) = next {
+            let (response, next_page) = api_page(self.client.as_ref(), url).await?;
+            let page: Vec<RemoteVersion> = deserialize_response(response)?;
+            versions.extend(page.into_iter().filter(|v| package.owns_tag(&v.tag_name)));
+            next = next_page;
+        }
+
         Ok(versions)
     }
+
+    async fn release(&self, package: &PackageSpec, tag: &str) -> Result<RemoteVersion> {
+        let url = format!("{}/tags/{tag}", package.releases_url());
+        let response = api(self.client.as_ref(), url).await?;
+        deserialize_response(response)
+    }
 }
@@ -3,30 +3,36 @@ use std::path::PathBuf;
 use fs4::FileExt;
 use tokio::task;
 
+use crate::domain::package::Package;
 use crate::ports::Lock;
 use crate::ports::LockGuard;
 use crate::ports::Paths;
 
-pub struct FileLock {
-    path: PathBuf,
+/// Serializes changes to a package's installation through a lock file in its
+/// downloads dir, so different packages can be installed concurrently.
+pub struct FileLock<P> {
+    paths: P,
 }
 
-impl FileLock {
-    pub async fn from_paths<P: Paths>(paths: &P) -> anyhow::Result<Self> {
-        let base = paths.installation_dir().await?;
-        Ok(Self {
-            path: base.join("hyper-jump.lock"),
-        })
-    }
+impl<P> FileLock<P> {
+    pub fn new(paths: P) -> Self { Self { paths } }
 }
 
-impl Lock for FileLock {
-    async fn acquire(&self) -> anyhow::Result<LockGuard> {
-        let path = self.path.clone();
+impl<P> Lock for FileLock<P>
+where
+    P: Paths,
+{
+    async fn acquire(&self, package: Package) -> anyhow::Result<LockGuard> {
+        let path: PathBuf = self.paths.downloads_dir(package).await?.join(".hyper-jump.lock");
+        let lock_path = path.clone();
         let file = task::spawn_blocking(move || -> anyhow::Result<std::fs::File> {
             use std::io::Write;
-            let mut file =
-                std::fs::OpenOptions::new().read(true).write(true).create(true).open(&path)?;
+            let mut file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
             file.lock_exclusive()?;
             let _ = file.set_len(0);
             let _ = writeln!(file, "pid:{}", std::process::id());
@@ -35,7 +41,7 @@ impl Lock for FileLock {
         .await??;
 
         Ok(LockGuard {
-            path: Some(self.path.clone()),
+            path: Some(lock_path),
             file: Some(file),
         })
     }
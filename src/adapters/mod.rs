@@ -1,5 +1,7 @@
 pub mod archive;
+pub mod cache;
 pub mod client;
+pub mod digest;
 pub mod dirs;
 pub mod downloader;
 pub mod env;
@@ -9,7 +11,9 @@ pub mod github_release;
 pub mod lock;
 pub mod output;
 pub mod path;
+pub mod pin_store;
 pub mod platform;
 pub mod process;
 pub mod proxy;
+pub mod registry_source;
 pub mod used_store;
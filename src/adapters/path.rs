@@ -14,17 +14,18 @@ impl FsPaths {
 
 impl Paths for FsPaths {
     async fn downloads_dir(&self, package: Package) -> anyhow::Result<PathBuf> {
-        let mut root = self.root_dir.clone();
-        let alias = package.alias();
-        root.push(alias);
+        let root = self.package_dir(&package);
         tokio::fs::create_dir_all(&root).await?;
         Ok(root)
     }
 
     async fn installation_dir(&self) -> anyhow::Result<PathBuf> {
-        let mut root = self.root_dir.clone();
-        root.push("bin");
+        let root = self.bin_dir();
         tokio::fs::create_dir_all(&root).await?;
         Ok(root)
     }
+
+    fn package_dir(&self, package: &Package) -> PathBuf { self.root_dir.join(package.alias()) }
+
+    fn bin_dir(&self) -> PathBuf { self.root_dir.join("bin") }
 }
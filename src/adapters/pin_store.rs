@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use config::Config;
+use config::File;
+use config::FileFormat;
+use serde::Deserialize;
+
+use crate::domain::package::Package;
+use crate::domain::version::PinnedVersion;
+use crate::ports::PinStore;
+
+pub const VERSIONS_FILE: &str = ".hj-versions";
+pub const PROJECT_FILE: &str = "hyper-jump.toml";
+
+#[derive(Debug, Default, Deserialize)]
+struct ProjectFile {
+    #[serde(default)]
+    versions: HashMap<String, String>,
+}
+
+/// Reads and writes per-project version pins.
+///
+/// Pins are looked up by walking from `start_dir` to the filesystem root. In
+/// each directory a `.hj-versions` file (one `<package> <version>` pair per
+/// line) takes precedence over the `[versions]` table of a `hyper-jump.toml`.
+pub struct ProjectPinStore {
+    start_dir: PathBuf,
+}
+
+impl ProjectPinStore {
+    pub fn new(start_dir: PathBuf) -> Self { Self { start_dir } }
+}
+
+/// Finds the nearest `hyper-jump.toml` from `start_dir` up to the filesystem
+/// root.
+pub fn find_project_file(start_dir: &Path) -> Option<PathBuf> {
+    start_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+impl PinStore for ProjectPinStore {
+    async fn pinned(&self, package: Package) -> anyhow::Result<Option<PinnedVersion>> {
+        let names = [package.spec().id.clone(), package.alias()];
+        for dir in self.start_dir.ancestors() {
+            let versions_file = dir.join(VERSIONS_FILE);
+            if let Some(version) = read_versions_file(&versions_file, &names).await? {
+                check_version(&version, &versions_file)?;
+                return Ok(Some(PinnedVersion {
+                    version,
+                    file: versions_file,
+                }));
+            }
+
+            let project_file = dir.join(PROJECT_FILE);
+            if let Some(version) = read_project_file(&project_file, &names).await? {
+                check_version(&version, &project_file)?;
+                return Ok(Some(PinnedVersion {
+                    version,
+                    file: project_file,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn pin(&self, package: Package, version: &str) -> anyhow::Result<PathBuf> {
+        let path = self.start_dir.join(VERSIONS_FILE);
+        let names = [package.spec().id.clone(), package.alias()];
+        let contents = read_optional(&path).await?.unwrap_or_default();
+
+        let mut lines: Vec<String> = contents
+            .lines()
+            .filter(|line| match parse_line(line) {
+                Some((name, _)) => !names.iter().any(|n| n == name),
+                None => true,
+            })
+            .map(str::to_string)
+            .collect();
+        lines.push(format!("{} {version}", package.spec().id));
+
+        tokio::fs::write(&path, format!("{}\n", lines.join("\n"))).await?;
+        Ok(path)
+    }
+}
+
+async fn read_versions_file(path: &Path, names: &[String]) -> anyhow::Result<Option<String>> {
+    let Some(contents) = read_optional(path).await? else {
+        return Ok(None);
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(parse_line)
+        .find(|(name, _)| names.iter().any(|n| n == name))
+        .map(|(_, version)| version.to_string()))
+}
+
+async fn read_project_file(path: &Path, names: &[String]) -> anyhow::Result<Option<String>> {
+    let Some(contents) = read_optional(path).await? else {
+        return Ok(None);
+    };
+
+    let parsed: ProjectFile = Config::builder()
+        .add_source(File::from_str(&contents, FileFormat::Toml))
+        .build()
+        .and_then(|config| config.try_deserialize())
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    Ok(names.iter().find_map(|name| parsed.versions.get(name).cloned()))
+}
+
+/// A pinned version names a directory under the package's downloads dir, so
+/// it has to be a single plain path component.
+fn check_version(version: &str, file: &Path) -> anyhow::Result<()> {
+    let mut components = Path::new(version).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(anyhow!(
+            "Invalid version '{version}' pinned in {}",
+            file.display()
+        )),
+    }
+}
+
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.split('#').next().unwrap_or_default();
+    let mut parts = line.split_whitespace();
+    Some((parts.next()?, parts.next()?))
+}
+
+async fn read_optional(path: &Path) -> anyhow::Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_pair_a_package_with_a_version() {
+        assert_eq!(parse_line("reth v1.0.0"), Some(("reth", "v1.0.0")));
+        assert_eq!(parse_line("  reth\t1.0.0  "), Some(("reth", "1.0.0")));
+        assert_eq!(parse_line("reth 1.0.0 # the node"), Some(("reth", "1.0.0")));
+        assert_eq!(parse_line("reth 1.0.0 extra"), Some(("reth", "1.0.0")));
+    }
+
+    #[test]
+    fn comments_and_incomplete_lines_are_skipped() {
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("# reth 1.0.0"), None);
+        assert_eq!(parse_line("reth"), None);
+        assert_eq!(parse_line("reth #1.0.0"), None);
+    }
+
+    #[test]
+    fn pinned_versions_must_be_a_plain_directory_name() {
+        let file = Path::new("/project/.hj-versions");
+        for version in ["v1.0.0", "1.0.0", "nightly", "v1.0.0-rc.1"] {
+            assert!(check_version(version, file).is_ok(), "{version}");
+        }
+        for version in ["", ".", "..", "../v1.0.0", "v1/v2", "/v1.0.0"] {
+            let err = check_version(version, file).unwrap_err();
+            assert!(
+                err.to_string().contains("/project/.hj-versions"),
+                "{version}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn versions_files_match_the_id_or_alias() {
+        let dir = std::env::temp_dir().join(format!("hj-pins-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VERSIONS_FILE);
+        std::fs::write(&path, "# pins\nother 2.0.0\nreth v1.0.0\n").unwrap();
+
+        let names = ["paradigmxyz/reth".to_string(), "reth".to_string()];
+        let version = read_versions_file(&path, &names).await.unwrap();
+        assert_eq!(version.as_deref(), Some("v1.0.0"));
+
+        let names = ["acme/tool".to_string(), "tool".to_string()];
+        assert_eq!(read_versions_file(&path, &names).await.unwrap(), None);
+        let missing = dir.join("missing");
+        assert_eq!(read_versions_file(&missing, &names).await.unwrap(), None);
+    }
+}
@@ -1,11 +1,8 @@
 use std::path::Path;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
+use std::process::ExitStatus;
 
 use anyhow::anyhow;
 use anyhow::Result;
-use tokio::time::sleep;
-use tokio::time::Duration;
 
 use crate::ports::Process;
 
@@ -14,77 +11,146 @@ pub struct TokioProcess;
 
 impl Process for TokioProcess {
     async fn run(&self, program: &Path, args: &[String]) -> anyhow::Result<()> {
-        let _term = Arc::new(AtomicBool::new(false));
-
         #[cfg(unix)]
         {
-            signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&_term))?;
+            use std::io::ErrorKind;
+            use std::os::unix::process::CommandExt;
+
+            // `exec` only returns when the process image could not be replaced.
+            let error = std::process::Command::new(program).args(args).exec();
+            if matches!(
+                error.kind(),
+                ErrorKind::NotFound | ErrorKind::PermissionDenied
+            ) {
+                return Err(anyhow!("Failed to execute {}: {error}", program.display()));
+            }
         }
 
-        let mut child = tokio::process::Command::new(program);
-        child.args(args);
-
-        let mut spawned_child = child.spawn()?;
-        watch_process(&mut spawned_child, &_term).await
+        spawn_and_wait(program, args).await
     }
+}
 
-    async fn output(&self, program: &Path, args: &[String]) -> anyhow::Result<Vec<u8>> {
-        let output = tokio::process::Command::new(program).args(args).output().await?;
-        Ok(output.stdout)
-    }
+/// Runs the program as a child, forwarding signals to it and mirroring how
+/// it exited. Used where the proxy cannot replace itself with `exec`.
+async fn spawn_and_wait(program: &Path, args: &[String]) -> Result<()> {
+    // Handlers go in before the child exists, so signals that arrive while it
+    // starts are delivered once it has.
+    #[cfg(unix)]
+    let forwarder = SignalForwarder::new()?;
+
+    let mut child = tokio::process::Command::new(program).args(args).spawn()?;
+
+    #[cfg(unix)]
+    forwarder.forward_to(child.id().ok_or_else(|| anyhow!("Child exited early"))?);
+
+    let status = child.wait().await?;
+
+    #[cfg(unix)]
+    forwarder.close();
+
+    exit_like(status)
 }
 
-async fn watch_process(
-    spawned_child: &mut tokio::process::Child,
-    term_signal: &Arc<AtomicBool>,
-) -> Result<()> {
-    tokio::select! {
-        status = spawned_child.wait() => handle_process_exit(status).await,
-        _ = tokio::signal::ctrl_c() => handle_ctrl_c(spawned_child, term_signal).await,
-    }
+/// Signals the terminal sends to its whole foreground process group, which
+/// the child is part of. They are caught, so the proxy outlives the child and
+/// reports how it exited, but not forwarded, or the child would get them
+/// twice.
+#[cfg(unix)]
+const TERMINAL_SIGNALS: [i32; 2] = [signal_hook::consts::SIGINT, signal_hook::consts::SIGQUIT];
+
+/// Relays the signals the proxy receives to the child. Signals that arrive
+/// before the child is known are held until it is.
+#[cfg(unix)]
+struct SignalForwarder {
+    handle: signal_hook::iterator::Handle,
+    target: std::sync::Arc<std::sync::Mutex<SignalTarget>>,
 }
 
-async fn handle_process_exit(
-    status: Result<std::process::ExitStatus, std::io::Error>,
-) -> Result<()> {
-    match status?.code() {
-        Some(0) => Ok(()),
-        Some(code) => {
-            std::process::exit(code);
+#[cfg(unix)]
+#[derive(Default)]
+struct SignalTarget {
+    pid: Option<nix::unistd::Pid>,
+    pending: Vec<nix::sys::signal::Signal>,
+}
+
+#[cfg(unix)]
+impl SignalForwarder {
+    fn new() -> Result<Self> {
+        use nix::sys::signal::kill;
+        use nix::sys::signal::Signal;
+        use signal_hook::consts::signal::*;
+        use signal_hook::iterator::Signals;
+
+        let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP, SIGQUIT, SIGUSR1, SIGUSR2])?;
+        let handle = signals.handle();
+        let target = std::sync::Arc::new(std::sync::Mutex::new(SignalTarget::default()));
+
+        let shared = target.clone();
+        std::thread::spawn(move || {
+            for signal in signals.forever() {
+                if TERMINAL_SIGNALS.contains(&signal) {
+                    continue;
+                }
+                let Ok(signal) = Signal::try_from(signal) else {
+                    continue;
+                };
+                let mut target = shared.lock().unwrap_or_else(|err| err.into_inner());
+                match target.pid {
+                    Some(pid) => {
+                        let _ = kill(pid, signal);
+                    }
+                    None => target.pending.push(signal),
+                }
+            }
+        });
+
+        Ok(Self { handle, target })
+    }
+
+    fn forward_to(&self, pid: u32) {
+        let pid = nix::unistd::Pid::from_raw(pid as i32);
+        let mut target = self.target.lock().unwrap_or_else(|err| err.into_inner());
+        target.pid = Some(pid);
+        for signal in std::mem::take(&mut target.pending) {
+            let _ = nix::sys::signal::kill(pid, signal);
         }
-        None => Err(anyhow!("Process terminated by signal")),
     }
+
+    fn close(&self) { self.handle.close() }
 }
 
-async fn handle_ctrl_c(
-    spawned_child: &mut tokio::process::Child,
-    term_signal: &Arc<AtomicBool>,
-) -> Result<()> {
-    term_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+fn exit_like(status: ExitStatus) -> Result<()> {
+    match status.code() {
+        Some(0) => return Ok(()),
+        Some(code) => std::process::exit(code),
+        None => {}
+    }
 
     #[cfg(unix)]
-    handle_unix_signals(spawned_child, term_signal)?;
+    {
+        use std::os::unix::process::ExitStatusExt;
 
-    sleep(Duration::from_millis(200)).await;
-    Ok(())
+        if let Some(signal) = status.signal() {
+            reraise(signal);
+        }
+    }
+
+    Err(anyhow!("Process terminated by signal"))
 }
 
+/// Terminates the proxy with the same signal that killed the child, so
+/// supervisors see the real cause instead of a generic failure.
 #[cfg(unix)]
-fn handle_unix_signals(
-    spawned_child: &mut tokio::process::Child,
-    term_signal: &Arc<AtomicBool>,
-) -> Result<()> {
-    use std::sync::atomic::Ordering;
-
+fn reraise(signal: i32) -> ! {
+    use nix::sys::signal;
+    use nix::sys::signal::SigHandler;
     use nix::sys::signal::Signal;
-    use nix::sys::signal::{self};
-    use nix::unistd::Pid;
 
-    if term_signal.load(Ordering::Relaxed) {
-        let pid = spawned_child.id().expect("Failed to get child process ID") as i32;
-        signal::kill(Pid::from_raw(pid), Signal::SIGUSR1)?;
-        term_signal.store(false, Ordering::Relaxed);
+    if let Ok(sig) = Signal::try_from(signal) {
+        // SAFETY: restoring the default disposition right before re-raising.
+        let _ = unsafe { signal::signal(sig, SigHandler::SigDfl) };
+        let _ = signal::raise(sig);
     }
 
-    Ok(())
+    std::process::exit(128 + signal)
 }
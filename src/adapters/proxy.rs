@@ -1,4 +1,6 @@
 use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -7,53 +9,57 @@ use tracing::info;
 use crate::ports::Env;
 use crate::ports::Fs;
 use crate::ports::Paths;
-use crate::ports::Process;
 use crate::ports::ProxyInstaller;
 
+/// Installs proxies as links to the running `hj` executable.
+///
+/// Each proxy in the installation dir is a symlink to `hj`, falling back to a
+/// hard link and finally a copy where links are not supported. Links are
+/// swapped in with a rename so a proxy is never observed half-written, and a
+/// proxy that already runs this `hj` is left alone.
 #[derive(Clone)]
-pub struct ProxyFsCopier<P, E, F, Proc> {
+pub struct ProxyFsLinker<P, E, F> {
     paths: P,
     env: E,
     fs: F,
-    process: Proc,
 }
 
-impl<P, E, F, Proc> ProxyFsCopier<P, E, F, Proc> {
-    pub fn new(paths: P, env: E, fs: F, process: Proc) -> Self {
-        Self {
-            paths,
-            env,
-            fs,
-            process,
-        }
-    }
+impl<P, E, F> ProxyFsLinker<P, E, F> {
+    pub fn new(paths: P, env: E, fs: F) -> Self { Self { paths, env, fs } }
 }
 
-impl<P, E, F, Proc> ProxyInstaller for ProxyFsCopier<P, E, F, Proc>
+impl<P, E, F> ProxyInstaller for ProxyFsLinker<P, E, F>
 where
     P: Paths + Clone + Send + Sync,
     E: Env + Clone + Send + Sync,
     F: Fs + Clone + Send + Sync,
-    Proc: Process + Clone + Send + Sync,
 {
-    async fn ensure_proxy(&self, alias: &str) -> anyhow::Result<()> {
-        let paths = self.paths.clone();
-        let env = self.env.clone();
-        let fs = self.fs.clone();
-        let process = self.process.clone();
-        copy_package_proxy(&paths, &env, &fs, &process, alias).await
+    async fn ensure_proxy(&self, name: &str) -> anyhow::Result<()> {
+        link_package_proxy(&self.paths, &self.env, &self.fs, name, false).await
+    }
+
+    async fn rebuild_proxy(&self, name: &str) -> anyhow::Result<()> {
+        link_package_proxy(&self.paths, &self.env, &self.fs, name, true).await
+    }
+
+    async fn remove_proxy(&self, name: &str) -> anyhow::Result<()> {
+        let proxy = self.paths.installation_dir().await?.join(name);
+        if self.fs.exists(&proxy).await? || self.fs.read_link(&proxy).await?.is_some() {
+            self.fs.remove_file(&proxy).await?;
+        }
+        Ok(())
     }
 }
 
-async fn copy_package_proxy(
+async fn link_package_proxy(
     paths: &impl Paths,
     env: &impl Env,
     fs: &impl Fs,
-    process: &impl Process,
-    alias: &str,
+    name: &str,
+    force: bool,
 ) -> Result<()> {
     let exe_path = env.current_exe()?;
-    let mut installation_dir = paths.installation_dir().await?;
+    let installation_dir = paths.installation_dir().await?;
 
     if !fs.exists(&installation_dir).await? {
         fs.ensure_dir(&installation_dir).await?;
@@ -61,19 +67,51 @@ async fn copy_package_proxy(
 
     add_to_path(env, &installation_dir)?;
 
-    installation_dir.push(alias);
-    let proxy_version = read_proxy_version(process, alias).await?;
-    if matches!(proxy_version, Some(version) if version == env!("CARGO_PKG_VERSION")) {
+    let proxy = installation_dir.join(name);
+    if !force && is_current(fs, &exe_path, &proxy).await? {
         return Ok(());
     }
 
-    fs.copy(&exe_path, &installation_dir)
-        .await
-        .map_err(|_| anyhow!("Could not copy the proxy"))?;
+    let staging = installation_dir.join(staging_name(name));
+    if fs.symlink(&exe_path, &staging).await.is_err()
+        && fs.hard_link(&exe_path, &staging).await.is_err()
+    {
+        fs.copy(&exe_path, &staging)
+            .await
+            .map_err(|_| anyhow!("Could not create the proxy for {name}"))?;
+    }
+
+    if fs.rename(&staging, &proxy).await.is_err() {
+        let _ = fs.remove_file(&staging).await;
+        return Err(anyhow!("Could not install the proxy for {name}"));
+    }
 
     Ok(())
 }
 
+/// Whether `proxy` already runs `exe`: a symlink to it, a hard link of it, or
+/// a copy of the same size made after it.
+async fn is_current(fs: &impl Fs, exe: &Path, proxy: &Path) -> Result<bool> {
+    if let Some(target) = fs.read_link(proxy).await? {
+        return Ok(target == exe);
+    }
+
+    let (Some(exe), Some(proxy)) = (fs.stamp(exe).await?, fs.stamp(proxy).await?) else {
+        return Ok(false);
+    };
+    let hard_link = (exe.dev, exe.ino) == (proxy.dev, proxy.ino);
+    let copy = exe.len == proxy.len && proxy.modified >= exe.modified;
+    Ok(hard_link || copy)
+}
+
+/// A staging name no other process, or task of this one, is using, so
+/// concurrent installs never link over each other's proxy.
+fn staging_name(name: &str) -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT.fetch_add(1, Ordering::Relaxed);
+    format!(".{name}.{}.{id}.tmp", std::process::id())
+}
+
 fn add_to_path(env: &impl Env, installation_dir: &Path) -> Result<()> {
     let installation_dir = installation_dir.to_str().unwrap();
 
@@ -85,17 +123,283 @@ fn add_to_path(env: &impl Env, installation_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-async fn read_proxy_version(process: &impl Process, alias: &str) -> Result<Option<String>> {
-    let version_arg = format!("--{}", env!("CARGO_BIN_NAME"));
-    let output = match process.output(Path::new(alias), &[version_arg]).await {
-        Ok(output) => output,
-        Err(_) => return Ok(None),
-    };
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::domain::package::Package;
+    use crate::ports::FileStamp;
+
+    const EXE: &str = "/opt/hj";
+    const BIN: &str = "/root/bin";
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Node {
+        Link(PathBuf),
+        File(FileStamp),
+    }
+
+    #[derive(Default)]
+    struct State {
+        nodes: HashMap<PathBuf, Node>,
+        next_ino: u64,
+        clock: u64,
+        renames: usize,
+    }
+
+    impl State {
+        fn file(&mut self, len: u64) -> Node {
+            self.next_ino += 1;
+            self.clock += 1;
+            Node::File(FileStamp {
+                dev: 1,
+                ino: self.next_ino,
+                len,
+                modified: SystemTime::UNIX_EPOCH + Duration::from_secs(self.clock),
+            })
+        }
+    }
+
+    /// An in-memory filesystem that can refuse symlinks and hard links, like
+    /// filesystems without them do.
+    #[derive(Clone)]
+    struct FakeFs {
+        state: Arc<Mutex<State>>,
+        symlinks: bool,
+        hard_links: bool,
+    }
+
+    impl FakeFs {
+        fn new(symlinks: bool, hard_links: bool) -> Self {
+            let fs = Self {
+                state: Arc::default(),
+                symlinks,
+                hard_links,
+            };
+            fs.replace_exe(1000);
+            fs
+        }
+
+        /// Stands for an upgrade of `hj` itself.
+        fn replace_exe(&self, len: u64) {
+            let mut state = self.state.lock().unwrap();
+            let exe = state.file(len);
+            state.nodes.insert(PathBuf::from(EXE), exe);
+        }
+
+        fn node(&self, path: &Path) -> Option<Node> {
+            self.state.lock().unwrap().nodes.get(path).cloned()
+        }
+
+        fn renames(&self) -> usize { self.state.lock().unwrap().renames }
+
+        fn paths(&self) -> Vec<PathBuf> {
+            let mut paths: Vec<_> = self.state.lock().unwrap().nodes.keys().cloned().collect();
+            paths.sort();
+            paths
+        }
+    }
+
+    impl Fs for FakeFs {
+        async fn ensure_dir(&self, _path: &Path) -> Result<()> { Ok(()) }
+
+        async fn copy(&self, src: &Path, dest: &Path) -> Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let Some(Node::File(src)) = state.nodes.get(src).cloned() else {
+                return Err(anyhow!("no such file"));
+            };
+            let copy = state.file(src.len);
+            state.nodes.insert(dest.to_path_buf(), copy);
+            Ok(())
+        }
+
+        async fn read_dir(&self, _path: &Path) -> Result<Vec<PathBuf>> { Ok(self.paths()) }
+
+        async fn remove_dir_all(&self, _path: &Path) -> Result<()> { unreachable!() }
+
+        async fn remove_file(&self, path: &Path) -> Result<()> {
+            let mut state = self.state.lock().unwrap();
+            state.nodes.remove(path).map(|_| ()).ok_or_else(|| anyhow!("no such file"))
+        }
+
+        async fn is_dir(&self, path: &Path) -> Result<bool> { Ok(path == Path::new(BIN)) }
+
+        async fn exists(&self, path: &Path) -> Result<bool> {
+            Ok(path == Path::new(BIN) || self.node(path).is_some())
+        }
+
+        async fn symlink(&self, target: &Path, link: &Path) -> Result<()> {
+            if !self.symlinks {
+                return Err(anyhow!("symlinks are not supported"));
+            }
+            let mut state = self.state.lock().unwrap();
+            state.nodes.insert(link.to_path_buf(), Node::Link(target.to_path_buf()));
+            Ok(())
+        }
+
+        async fn hard_link(&self, src: &Path, dest: &Path) -> Result<()> {
+            if !self.hard_links {
+                return Err(anyhow!("hard links are not supported"));
+            }
+            let mut state = self.state.lock().unwrap();
+            let src = state.nodes.get(src).cloned().ok_or_else(|| anyhow!("no such file"))?;
+            state.nodes.insert(dest.to_path_buf(), src);
+            Ok(())
+        }
+
+        async fn read_link(&self, path: &Path) -> Result<Option<PathBuf>> {
+            match self.node(path) {
+                Some(Node::Link(target)) => Ok(Some(target)),
+                _ => Ok(None),
+            }
+        }
+
+        async fn rename(&self, src: &Path, dest: &Path) -> Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let node = state.nodes.remove(src).ok_or_else(|| anyhow!("no such file"))?;
+            state.nodes.insert(dest.to_path_buf(), node);
+            state.renames += 1;
+            Ok(())
+        }
+
+        async fn modified(&self, _path: &Path) -> Result<SystemTime> { unreachable!() }
+
+        async fn read_to_string(&self, _path: &Path) -> Result<String> { unreachable!() }
+
+        async fn write(&self, _path: &Path, _contents: &str) -> Result<()> { unreachable!() }
+
+        async fn dir_size(&self, _path: &Path) -> Result<u64> { unreachable!() }
+
+        async fn stamp(&self, path: &Path) -> Result<Option<FileStamp>> {
+            match self.node(path) {
+                Some(Node::File(stamp)) => Ok(Some(stamp)),
+                Some(Node::Link(target)) => Ok(Some(FileStamp {
+                    dev: 1,
+                    ino: u64::MAX,
+                    len: target.as_os_str().len() as u64,
+                    modified: SystemTime::UNIX_EPOCH,
+                })),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct FakeEnv;
+
+    impl Env for FakeEnv {
+        fn exe_name(&self) -> String { "hj".to_string() }
+        fn args(&self) -> Vec<String> { Vec::new() }
+        fn root_dir(&self) -> Option<PathBuf> { None }
+        fn cache_dir(&self) -> Option<PathBuf> { None }
+        fn packages_file(&self) -> Option<PathBuf> { None }
+        fn home_dir(&self) -> Option<PathBuf> { None }
+        fn current_exe(&self) -> Result<PathBuf> { Ok(PathBuf::from(EXE)) }
+        fn path_var(&self) -> Option<String> { Some(BIN.to_string()) }
+        fn current_dir(&self) -> Result<PathBuf> { Ok(PathBuf::from("/")) }
+    }
+
+    #[derive(Clone)]
+    struct FakePaths;
+
+    impl Paths for FakePaths {
+        async fn downloads_dir(&self, _package: Package) -> Result<PathBuf> { unreachable!() }
+        async fn installation_dir(&self) -> Result<PathBuf> { Ok(self.bin_dir()) }
+        fn package_dir(&self, _package: &Package) -> PathBuf { unreachable!() }
+        fn bin_dir(&self) -> PathBuf { PathBuf::from(BIN) }
+    }
+
+    fn linker(fs: &FakeFs) -> ProxyFsLinker<FakePaths, FakeEnv, FakeFs> {
+        ProxyFsLinker::new(FakePaths, FakeEnv, fs.clone())
+    }
+
+    fn proxy() -> PathBuf { Path::new(BIN).join("tool") }
+
+    #[tokio::test]
+    async fn proxies_fall_back_from_symlinks_to_hard_links_to_copies() {
+        let fs = FakeFs::new(true, true);
+        linker(&fs).ensure_proxy("tool").await.unwrap();
+        assert_eq!(fs.node(&proxy()), Some(Node::Link(PathBuf::from(EXE))));
+
+        let fs = FakeFs::new(false, true);
+        linker(&fs).ensure_proxy("tool").await.unwrap();
+        assert_eq!(fs.node(&proxy()), fs.node(Path::new(EXE)));
+
+        let fs = FakeFs::new(false, false);
+        linker(&fs).ensure_proxy("tool").await.unwrap();
+        let (Some(Node::File(copy)), Some(Node::File(exe))) =
+            (fs.node(&proxy()), fs.node(Path::new(EXE)))
+        else {
+            panic!("the proxy should be a copy");
+        };
+        assert_ne!(copy.ino, exe.ino);
+        assert_eq!(copy.len, exe.len);
+
+        // Nothing is left behind in the installation dir.
+        assert_eq!(fs.paths(), vec![PathBuf::from(EXE), proxy()]);
+    }
+
+    #[tokio::test]
+    async fn current_proxies_are_left_alone() {
+        for (symlinks, hard_links) in [(true, true), (false, true), (false, false)] {
+            let fs = FakeFs::new(symlinks, hard_links);
+            let linker = linker(&fs);
+            linker.ensure_proxy("tool").await.unwrap();
+            linker.ensure_proxy("tool").await.unwrap();
+            assert_eq!(
+                fs.renames(),
+                1,
+                "symlinks: {symlinks}, hard links: {hard_links}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_proxies_are_replaced() {
+        let fs = FakeFs::new(false, false);
+        let linker = linker(&fs);
+        linker.ensure_proxy("tool").await.unwrap();
+
+        fs.replace_exe(2000);
+        linker.ensure_proxy("tool").await.unwrap();
+        assert_eq!(fs.renames(), 2);
+        let Some(Node::File(copy)) = fs.node(&proxy()) else {
+            panic!("the proxy should be a copy");
+        };
+        assert_eq!(copy.len, 2000);
+
+        // A symlink to another executable is replaced too.
+        let fs = FakeFs::new(true, true);
+        fs.symlink(Path::new("/old/hj"), &proxy()).await.unwrap();
+        self::linker(&fs).ensure_proxy("tool").await.unwrap();
+        assert_eq!(fs.node(&proxy()), Some(Node::Link(PathBuf::from(EXE))));
+    }
+
+    #[tokio::test]
+    async fn reshim_rebuilds_current_proxies() {
+        for (symlinks, hard_links) in [(true, true), (false, true), (false, false)] {
+            let fs = FakeFs::new(symlinks, hard_links);
+            let linker = linker(&fs);
+            linker.ensure_proxy("tool").await.unwrap();
+            linker.rebuild_proxy("tool").await.unwrap();
+            assert_eq!(
+                fs.renames(),
+                2,
+                "symlinks: {symlinks}, hard links: {hard_links}"
+            );
+            assert_eq!(fs.paths(), vec![PathBuf::from(EXE), proxy()]);
+        }
+    }
 
-    let version = String::from_utf8(output)?.trim().to_string();
-    if version.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(version))
+    #[test]
+    fn staging_names_are_unique() {
+        assert_ne!(staging_name("tool"), staging_name("tool"));
+        assert!(staging_name("tool").starts_with(".tool."));
     }
 }
@@ -0,0 +1,422 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use reqwest::header::ETAG;
+use reqwest::header::IF_NONE_MATCH;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::info;
+use tracing::warn;
+
+use crate::adapters::client::require;
+use crate::adapters::digest::sha256_hex;
+use crate::domain::package::RegistryLayer;
+use crate::domain::package::RegistrySource;
+use crate::ports::RegistrySources;
+use crate::ports::SourceRefresh;
+
+const REGISTRY_FILE: &str = "packages.toml";
+const META_FILE: &str = "meta.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SourceMeta {
+    fetched_at: u64,
+    etag: Option<String>,
+}
+
+/// Keeps a copy of each registry source under `dir`, in a directory named
+/// after the hash of its location, along with when it was fetched and the
+/// `ETag` it came with.
+pub struct CachedRegistrySources {
+    dir: PathBuf,
+    client: Option<reqwest::Client>,
+}
+
+impl CachedRegistrySources {
+    pub fn new(dir: PathBuf, client: Option<&reqwest::Client>) -> Self {
+        Self {
+            dir,
+            client: client.cloned(),
+        }
+    }
+
+    async fn fetch(&self, source: &RegistrySource, dir: &Path, meta: &SourceMeta) -> Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        let etag = match (&source.url, &source.git) {
+            (Some(url), _) => self.fetch_url(url, dir, meta.etag.as_deref()).await?,
+            (None, Some(git)) => {
+                fetch_git(git, source.rev.as_deref(), &source.path, dir).await?;
+                None
+            }
+            (None, None) => return Err(anyhow!("Registry source without 'url' or 'git'")),
+        };
+
+        let meta = SourceMeta {
+            fetched_at: now(),
+            etag,
+        };
+        tokio::fs::write(dir.join(META_FILE), serde_json::to_string(&meta)?).await?;
+        Ok(())
+    }
+
+    /// Downloads `url` unless the server reports the cached copy as current.
+    async fn fetch_url(&self, url: &str, dir: &Path, etag: Option<&str>) -> Result<Option<String>> {
+        let mut request = require(self.client.as_ref())?.get(url);
+        if let Some(etag) = etag.filter(|_| dir.join(REGISTRY_FILE).exists()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(etag.map(str::to_string));
+        }
+
+        let response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let contents = response.text().await?;
+        write_registry(dir, &contents).await?;
+        Ok(etag)
+    }
+}
+
+impl RegistrySources for CachedRegistrySources {
+    async fn load(
+        &self,
+        source: &RegistrySource,
+        refresh: SourceRefresh,
+    ) -> Result<Option<RegistryLayer>> {
+        let location = source.location();
+        let dir = self.dir.join(sha256_hex(&location));
+        let registry = dir.join(REGISTRY_FILE);
+        let meta = read_meta(&dir).await?;
+        let cached = registry.exists();
+
+        let stale = match meta.as_ref() {
+            Some(meta) => now().saturating_sub(meta.fetched_at) >= source.ttl()?.as_secs(),
+            None => true,
+        };
+        let fetch = match refresh {
+            SourceRefresh::CacheOnly => false,
+            SourceRefresh::IfStale => stale || !cached,
+            SourceRefresh::Always => true,
+        };
+
+        if fetch {
+            match self.fetch(source, &dir, &meta.unwrap_or_default()).await {
+                Ok(()) => info!("Fetched registry source {location}"),
+                Err(err) if cached => {
+                    warn!(
+                        "Failed to refresh registry source {location}, using the cached copy: \
+                         {err}"
+                    )
+                }
+                Err(err) if refresh == SourceRefresh::Always => {
+                    return Err(err.context(format!("Failed to fetch registry source {location}")))
+                }
+                Err(err) => {
+                    warn!("Failed to fetch registry source {location}, skipping it: {err}");
+                    return Ok(None);
+                }
+            }
+        }
+
+        if !registry.exists() {
+            warn!("Registry source {location} was never fetched, run `hj registry update`");
+            return Ok(None);
+        }
+
+        let mut layer = RegistryLayer::read(&registry)?;
+        layer.source = location;
+        Ok(Some(layer))
+    }
+}
+
+/// Shallow-clones the repository, or updates the existing clone, and copies
+/// `path` out of it.
+async fn fetch_git(git: &str, rev: Option<&str>, path: &str, dir: &Path) -> Result<()> {
+    let checkout = dir.join("repo");
+    let rev = rev.unwrap_or("HEAD");
+    if !checkout.join(".git").exists() {
+        run_git(&["init", "-q", &checkout.to_string_lossy()], None).await?;
+        run_git(&["remote", "add", "--", "origin", git], Some(&checkout)).await?;
+    }
+    run_git(
+        &["fetch", "-q", "--depth", "1", "--", "origin", rev],
+        Some(&checkout),
+    )
+    .await?;
+    run_git(
+        &["checkout", "-q", "--force", "FETCH_HEAD"],
+        Some(&checkout),
+    )
+    .await?;
+
+    let contents = tokio::fs::read_to_string(checkout.join(path))
+        .await
+        .with_context(|| format!("No {path} in {git}"))?;
+    write_registry(dir, &contents).await
+}
+
+async fn run_git(args: &[&str], cwd: Option<&Path>) -> Result<()> {
+    let mut command = tokio::process::Command::new("git");
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let output = command.args(args).output().await.context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces the cached registry file in one step, so readers never see it
+/// half-written.
+async fn write_registry(dir: &Path, contents: &str) -> Result<()> {
+    let staged = dir.join(format!("{REGISTRY_FILE}.tmp"));
+    tokio::fs::write(&staged, contents).await?;
+    tokio::fs::rename(&staged, dir.join(REGISTRY_FILE)).await?;
+    Ok(())
+}
+
+async fn read_meta(dir: &Path) -> Result<Option<SourceMeta>> {
+    match tokio::fs::read_to_string(dir.join(META_FILE)).await {
+        Ok(contents) => Ok(serde_json::from_str(&contents).ok()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const REGISTRY: &str = "[[package]]\nid = \"acme/tool\"\n";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hj-registry-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sources(dir: &Path) -> CachedRegistrySources {
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        CachedRegistrySources::new(dir.to_path_buf(), Some(&client))
+    }
+
+    fn url_source(url: &str) -> RegistrySource {
+        RegistrySource {
+            url: Some(url.to_string()),
+            git: None,
+            path: REGISTRY_FILE.to_string(),
+            rev: None,
+            ttl: None,
+        }
+    }
+
+    fn raw_response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {status}\r\nConnection: close\r\n");
+        for (name, value) in headers {
+            response.push_str(&format!("{name}: {value}\r\n"));
+        }
+        response.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+        response
+    }
+
+    /// Answers each connection with the next of `responses`, then with server
+    /// errors, and records the requests so tests can tell whether a load went
+    /// to the network.
+    async fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/packages.toml", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            let mut responses = responses.into_iter();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+                seen.lock().unwrap().push(String::from_utf8_lossy(&request).to_lowercase());
+                let response = responses
+                    .next()
+                    .unwrap_or_else(|| raw_response("500 Internal Server Error", &[], ""));
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn age_cache(dir: &Path, source: &RegistrySource) {
+        let meta_path = dir.join(sha256_hex(&source.location())).join(META_FILE);
+        let mut meta: SourceMeta =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta.fetched_at = 0;
+        std::fs::write(meta_path, serde_json::to_string(&meta).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn url_sources_are_revalidated_once_stale() {
+        let dir = scratch_dir("ttl");
+        let (url, requests) = serve(vec![
+            raw_response("200 OK", &[("ETag", "\"v1\"")], REGISTRY),
+            raw_response("304 Not Modified", &[], ""),
+        ])
+        .await;
+        let source = url_source(&url);
+        let cache = sources(&dir);
+
+        let layer = cache.load(&source, SourceRefresh::IfStale).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+        assert_eq!(layer.source, url);
+
+        // Within the TTL the cached copy is used as is.
+        cache.load(&source, SourceRefresh::IfStale).await.unwrap().unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        age_cache(&dir, &source);
+        let layer = cache.load(&source, SourceRefresh::IfStale).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        let meta =
+            std::fs::read_to_string(dir.join(sha256_hex(&source.location())).join(META_FILE))
+                .unwrap();
+        assert!(meta.contains("\\\"v1\\\""));
+    }
+
+    #[tokio::test]
+    async fn cache_only_loads_never_fetch() {
+        let dir = scratch_dir("cache-only");
+        let (url, requests) = serve(vec![raw_response("200 OK", &[], REGISTRY)]).await;
+        let source = url_source(&url);
+        let cache = sources(&dir);
+
+        assert!(cache.load(&source, SourceRefresh::CacheOnly).await.unwrap().is_none());
+        assert!(requests.lock().unwrap().is_empty());
+
+        cache.load(&source, SourceRefresh::Always).await.unwrap().unwrap();
+        age_cache(&dir, &source);
+        let layer = cache.load(&source, SourceRefresh::CacheOnly).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_refreshes_keep_the_cached_copy() {
+        let dir = scratch_dir("failed");
+        let (url, requests) = serve(vec![raw_response("200 OK", &[], REGISTRY)]).await;
+        let source = url_source(&url);
+        let cache = sources(&dir);
+
+        cache.load(&source, SourceRefresh::Always).await.unwrap().unwrap();
+        let layer = cache.load(&source, SourceRefresh::Always).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // Without a cached copy a forced refresh fails, and a lazy one skips
+        // the source.
+        let uncached = sources(&scratch_dir("failed-uncached"));
+        assert!(uncached.load(&source, SourceRefresh::Always).await.is_err());
+        assert!(uncached.load(&source, SourceRefresh::IfStale).await.unwrap().is_none());
+    }
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=hj", "-c", "user.email=hj@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[tokio::test]
+    async fn git_sources_copy_the_file_at_the_fetched_rev() {
+        let dir = scratch_dir("git");
+        let repo = dir.join("upstream");
+        std::fs::create_dir_all(repo.join("registry")).unwrap();
+        git(&repo, &["init", "-q"]);
+        std::fs::write(repo.join("registry/packages.toml"), REGISTRY).unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "first"]);
+
+        let source = RegistrySource {
+            url: None,
+            git: Some(format!("file://{}", repo.display())),
+            path: "registry/packages.toml".to_string(),
+            rev: None,
+            ttl: None,
+        };
+        let cache = sources(&dir.join("cache"));
+
+        let layer = cache.load(&source, SourceRefresh::IfStale).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+
+        let updated = format!("{REGISTRY}alias = \"tool\"\n");
+        std::fs::write(repo.join("registry/packages.toml"), &updated).unwrap();
+        git(&repo, &["commit", "-q", "-am", "second"]);
+
+        let layer = cache.load(&source, SourceRefresh::IfStale).await.unwrap().unwrap();
+        assert_eq!(layer.contents, REGISTRY);
+        let layer = cache.load(&source, SourceRefresh::Always).await.unwrap().unwrap();
+        assert_eq!(layer.contents, updated);
+    }
+
+    #[tokio::test]
+    async fn git_sources_need_the_path_in_the_repo() {
+        let dir = scratch_dir("git-missing");
+        let repo = dir.join("upstream");
+        std::fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        std::fs::write(repo.join("README"), "").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "first"]);
+
+        let git_url = format!("file://{}", repo.display());
+        let cache = dir.join("cache");
+        std::fs::create_dir_all(&cache).unwrap();
+        let err = fetch_git(&git_url, None, REGISTRY_FILE, &cache).await.unwrap_err();
+        assert!(err.to_string().contains("No packages.toml"));
+    }
+}
@@ -17,7 +17,7 @@ where
     P: Paths,
 {
     async fn current(&self, package: Package) -> anyhow::Result<Option<String>> {
-        let mut path: PathBuf = self.paths.downloads_dir(package).await?;
+        let mut path: PathBuf = self.paths.package_dir(&package);
         path.push("used");
         match tokio::fs::read_to_string(&path).await {
             Ok(contents) => Ok(Some(contents.trim().to_string())),
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Table;
+use indicatif::HumanBytes;
+use tracing::info;
+
+use crate::ports::DownloadCache;
+use crate::ports::Output;
+use crate::OutputFormat;
+
+pub async fn list_cache(
+    fmt: OutputFormat,
+    cache: &impl DownloadCache,
+    output: &impl Output,
+) -> Result<()> {
+    let entries = cache.entries().await?;
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&entries)?)?,
+        OutputFormat::Table => {
+            if entries.is_empty() {
+                info!("The download cache is empty");
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+            table.set_header(vec!["Url", "Sha256", "Size"]);
+            for entry in &entries {
+                table.add_row(vec![
+                    entry.url.clone(),
+                    entry.digest.chars().take(12).collect(),
+                    HumanBytes(entry.size).to_string(),
+                ]);
+            }
+            output.write_line(&table.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn cache_size(
+    fmt: OutputFormat,
+    cache: &impl DownloadCache,
+    output: &impl Output,
+) -> Result<()> {
+    let usage = cache.usage().await?;
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string(&usage)?),
+        OutputFormat::Table => output.write_line(&format!(
+            "{} in {} archives",
+            HumanBytes(usage.size),
+            usage.archives
+        )),
+    }
+}
+
+pub async fn clean_cache(cache: &impl DownloadCache) -> Result<()> {
+    let usage = cache.usage().await?;
+    cache.clean().await?;
+    info!(
+        "Removed {} cached archives ({})",
+        usage.archives,
+        HumanBytes(usage.size)
+    );
+    Ok(())
+}
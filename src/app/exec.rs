@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+
+use crate::app::install::install_version;
+use crate::app::installed::installed_match;
+use crate::app::installed::installed_versions;
+use crate::app::proxy::handle_package_process;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::ports::Archive;
+use crate::ports::DownloadCache;
+use crate::ports::Downloader;
+use crate::ports::Fs;
+use crate::ports::Lock;
+use crate::ports::Paths;
+use crate::ports::PinStore;
+use crate::ports::Platform;
+use crate::ports::Process;
+use crate::ports::ProxyInstaller;
+use crate::ports::ReleaseProvider;
+use crate::ports::UsedVersionStore;
+
+/// Runs `binary` of `spec` at `requested_version` once, leaving the used
+/// version and project pins alone. Without a version it runs what the proxy
+/// would. A version that is not installed is installed first when
+/// `install_missing` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn exec<R, D, A, F>(
+    spec: Arc<PackageSpec>,
+    binary: &str,
+    requested_version: Option<String>,
+    args: &[String],
+    install_missing: bool,
+    release_provider: &R,
+    downloader: &D,
+    archive: &A,
+    fs: &F,
+    platform: &impl Platform,
+    lock: &impl Lock,
+    used_store: &impl UsedVersionStore,
+    pins: &impl PinStore,
+    paths: &impl Paths,
+    proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+    process: &impl Process,
+    offline: bool,
+) -> anyhow::Result<()>
+where
+    R: ReleaseProvider,
+    D: Downloader,
+    A: Archive,
+    F: Fs,
+{
+    let package = Package::from_spec(spec.clone(), platform)?.with_binary(binary);
+    let version = match requested_version {
+        None => None,
+        Some(requested) => {
+            let downloads_dir = paths.downloads_dir(package.clone()).await?;
+            let installed = installed_versions(fs, &downloads_dir).await.unwrap_or_default();
+            match installed_match(&spec, &requested, &installed) {
+                Some(version) => Some(version),
+                None if install_missing => {
+                    let installed = install_version(
+                        spec.clone(),
+                        requested,
+                        release_provider,
+                        downloader,
+                        archive,
+                        fs,
+                        platform,
+                        lock,
+                        paths,
+                        proxy,
+                        cache,
+                        offline,
+                    )
+                    .await?;
+                    installed.version().map(|version| version.tag_name)
+                }
+                None => {
+                    return Err(anyhow!(
+                        "No installed version of {} matches '{requested}'. Pass --install to \
+                         fetch it.",
+                        spec.id
+                    ));
+                }
+            }
+        }
+    };
+
+    handle_package_process(
+        args, package, version, paths, used_store, pins, fs, platform, process,
+    )
+    .await
+}
@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Cell;
+use comfy_table::Color;
+use comfy_table::Table;
+use serde::Serialize;
+
+use crate::app::installed::installed_versions;
+use crate::app::resolve::resolve_requested_version;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_normal_version;
+use crate::ports::Fs;
+use crate::ports::Output;
+use crate::ports::Paths;
+use crate::ports::Platform;
+use crate::ports::ReleaseProvider;
+use crate::ports::UsedVersionStore;
+use crate::OutputFormat;
+
+#[derive(Serialize)]
+struct PackageInfo {
+    id: String,
+    alias: String,
+    repo: String,
+    org: String,
+    os: String,
+    arch: String,
+    platform_tag: Option<String>,
+    file_type: Option<String>,
+    version: Option<String>,
+    download_url: Option<String>,
+    checksum_url: Option<String>,
+    binary_path: Option<String>,
+    binaries: Vec<BinaryInfo>,
+    installed: Vec<String>,
+    used: Option<String>,
+    problems: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BinaryInfo {
+    name: String,
+    proxy: PathBuf,
+    target: Option<PathBuf>,
+}
+
+/// Shows what a spec resolves to on this platform for `requested_version`,
+/// along with its local state. Template errors are reported next to the
+/// values instead of aborting, since they are usually why this is run.
+#[allow(clippy::too_many_arguments)]
+pub async fn info(
+    spec: Arc<PackageSpec>,
+    requested_version: String,
+    fmt: OutputFormat,
+    provider: &impl ReleaseProvider,
+    platform: &impl Platform,
+    paths: &impl Paths,
+    used_store: &impl UsedVersionStore,
+    fs: &impl Fs,
+    output: &impl Output,
+) -> Result<()> {
+    let mut problems = Vec::new();
+    let package = Package::from_spec(spec.clone(), platform).ok();
+    let (installed, used, downloads_dir) = match package {
+        Some(package) => {
+            // Only looks, so nothing is created for a package never installed.
+            let downloads_dir = paths.package_dir(&package);
+            let mut installed = match fs.exists(&downloads_dir).await? {
+                true => installed_versions(fs, &downloads_dir).await?,
+                false => Vec::new(),
+            };
+            installed.sort_by_cached_key(|version| Reverse(spec.version(version)));
+            let used = used_store.current(package).await?;
+            (installed, used, Some(downloads_dir))
+        }
+        None => (Vec::new(), None, None),
+    };
+
+    let resolved = note(
+        &mut problems,
+        resolve_requested_version(&requested_version, &spec, &installed, provider).await,
+    );
+
+    let naming = match &resolved {
+        Some(version) => spec.for_version(version),
+        None => Cow::Borrowed(spec.as_ref()),
+    };
+    let platform_tag = note(&mut problems, naming.platform_tag(platform));
+    let file_type = note(&mut problems, naming.file_type(platform));
+    let binary_path = note(&mut problems, naming.binary_path(platform));
+    let (download_url, checksum_url) = match &resolved {
+        Some(version) => (
+            note(&mut problems, spec.download_url(version, platform)),
+            note(&mut problems, spec.checksum_url(version, platform)).flatten(),
+        ),
+        None => (None, None),
+    };
+
+    // Proxies run the used version, whose binary path may differ from the
+    // requested one's through an asset override.
+    let used_binary_path = match &used {
+        Some(used) => {
+            spec.for_version(&parse_normal_version(used).await?).binary_path(platform).ok()
+        }
+        None => None,
+    };
+
+    let installation_dir = paths.bin_dir();
+    let binaries = spec
+        .binary_names()
+        .into_iter()
+        .map(|name| BinaryInfo {
+            proxy: installation_dir.join(&name),
+            target: match (&downloads_dir, &used, &used_binary_path) {
+                (Some(dir), Some(used), Some(binary_path)) => {
+                    Some(dir.join(used).join(binary_path).join(&name))
+                }
+                _ => None,
+            },
+            name,
+        })
+        .collect();
+
+    let info = PackageInfo {
+        id: spec.id.clone(),
+        alias: spec.alias.clone(),
+        repo: spec.repo.clone(),
+        org: spec.org().to_string(),
+        os: platform.os().to_string(),
+        arch: platform.arch().to_string(),
+        platform_tag,
+        file_type,
+        version: resolved.map(|version| version.tag_name),
+        download_url,
+        checksum_url,
+        binary_path,
+        binaries,
+        installed,
+        used,
+        problems,
+    };
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&info)?),
+        OutputFormat::Table => output.write_line(&table(&info).to_string()),
+    }
+}
+
+fn table(info: &PackageInfo) -> Table {
+    let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let binaries = info
+        .binaries
+        .iter()
+        .map(|binary| match &binary.target {
+            Some(target) => format!("{} -> {}", binary.proxy.display(), target.display()),
+            None => binary.proxy.display().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+    table.add_row(vec!["Id", &info.id]);
+    table.add_row(vec!["Alias", &info.alias]);
+    table.add_row(vec!["Repo", &info.repo]);
+    table.add_row(vec!["Org", &info.org]);
+    table.add_row(vec!["Platform", &format!("{} {}", info.os, info.arch)]);
+    table.add_row(vec!["Platform tag", &value(&info.platform_tag)]);
+    table.add_row(vec!["File type", &value(&info.file_type)]);
+    table.add_row(vec!["Version", &value(&info.version)]);
+    table.add_row(vec!["Download url", &value(&info.download_url)]);
+    table.add_row(vec!["Checksum url", &value(&info.checksum_url)]);
+    table.add_row(vec!["Binary path", &value(&info.binary_path)]);
+    table.add_row(vec!["Proxies", &binaries]);
+    table.add_row(vec!["Installed", &info.installed.join(", ")]);
+    table.add_row(vec!["Used", &value(&info.used)]);
+    for problem in &info.problems {
+        table.add_row(vec![
+            Cell::new("Problem").fg(Color::Red),
+            Cell::new(problem).fg(Color::Red),
+        ]);
+    }
+    table
+}
+
+/// Keeps the value of `result`, or records its error as a problem.
+fn note<T>(problems: &mut Vec<String>, result: Result<T>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(err) => {
+            problems.push(err.to_string());
+            None
+        }
+    }
+}
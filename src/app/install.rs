@@ -2,15 +2,21 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use tracing::info;
 
 use crate::app::download;
-use crate::app::resolve::resolve_requested_version;
+use crate::app::installed::installed_dir;
+use crate::app::installed::installed_versions;
+use crate::app::resolve::resolve_for_install;
+use crate::domain::checksum::expected_digest;
 use crate::domain::package::Package;
 use crate::domain::package::PackageSpec;
 use crate::domain::version::LocalVersion;
 use crate::domain::version::ParsedVersion;
+use crate::domain::version::ReleaseStamp;
 use crate::ports::Archive;
+use crate::ports::DownloadCache;
 use crate::ports::Downloader;
 use crate::ports::Fs;
 use crate::ports::Paths;
@@ -18,6 +24,10 @@ use crate::ports::Platform;
 use crate::ports::ProxyInstaller;
 use crate::ports::ReleaseProvider;
 
+/// Records which build of a moving tag is installed in its version directory.
+pub const RELEASE_STAMP_FILE: &str = ".hj-release";
+
+/// Installs `requested_version` and marks it as used if no version is yet.
 #[allow(clippy::too_many_arguments)]
 pub async fn install<R, D, A, F>(
     spec: Arc<PackageSpec>,
@@ -31,6 +41,8 @@ pub async fn install<R, D, A, F>(
     used_store: &impl crate::ports::UsedVersionStore,
     paths: &impl Paths,
     proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+    offline: bool,
 ) -> anyhow::Result<()>
 where
     R: ReleaseProvider,
@@ -38,32 +50,162 @@ where
     A: Archive,
     F: Fs,
 {
-    let _guard = lock.acquire().await?;
-    let parsed_version: ParsedVersion =
-        resolve_requested_version(&requested_version, &spec, release_provider).await?;
-
-    let package = Package::with_parsed(spec.clone(), parsed_version.clone(), platform)?;
-    let root: PathBuf = paths.downloads_dir(package.clone()).await?;
-    fs.ensure_dir(&root).await?;
-    fs.set_current_dir(&root).await?;
+    let package = install_version(
+        spec,
+        requested_version,
+        release_provider,
+        downloader,
+        archive,
+        fs,
+        platform,
+        lock,
+        paths,
+        proxy,
+        cache,
+        offline,
+    )
+    .await?;
 
-    if version_exists(fs, &parsed_version.tag_name, &root).await? {
-        info!("{} is already installed.", parsed_version.tag_name);
-        proxy.ensure_proxy(&package.alias()).await?;
-        return Ok(());
+    // If nothing marked as used yet, set this one.
+    if let Some(version) = package.version() {
+        if used_store.current(package.clone()).await?.is_none() {
+            used_store.set_current(package, &version.tag_name).await?;
+        }
     }
 
-    proxy.ensure_proxy(&package.alias()).await?;
+    Ok(())
+}
 
-    // If nothing marked as used yet, set this one.
-    if used_store.current(package.clone()).await?.is_none() {
-        used_store.set_current(package.clone(), &parsed_version.tag_name).await?;
-    }
+/// Installs `requested_version` without touching the used version, and
+/// returns the package at the version it resolved to.
+#[allow(clippy::too_many_arguments)]
+pub async fn install_version<R, D, A, F>(
+    spec: Arc<PackageSpec>,
+    requested_version: String,
+    release_provider: &R,
+    downloader: &D,
+    archive: &A,
+    fs: &F,
+    platform: &impl Platform,
+    lock: &impl crate::ports::Lock,
+    paths: &impl Paths,
+    proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+    offline: bool,
+) -> anyhow::Result<Package>
+where
+    R: ReleaseProvider,
+    D: Downloader,
+    A: Archive,
+    F: Fs,
+{
+    let root: PathBuf = paths.downloads_dir(Package::from_spec(spec.clone(), platform)?).await?;
+    let resolved: ParsedVersion = resolve_for_install(
+        &requested_version,
+        &spec,
+        &installed_versions(fs, &root).await.unwrap_or_default(),
+        platform,
+        cache,
+        release_provider,
+        offline,
+    )
+    .await?;
+
+    let package = Package::with_parsed(spec.clone(), resolved.clone(), platform)?;
+    let _guard = lock.acquire(package.clone()).await?;
+    fs.ensure_dir(&root).await?;
 
-    let file_type = spec.file_type(platform)?;
+    // The version may already be installed under another spelling, which is
+    // then the tag it goes by.
+    let installed = installed_dir(&spec, fs, &root, &resolved.tag_name).await?;
+    let parsed_version = match &installed {
+        Some(name) => ParsedVersion {
+            tag_name: name.clone(),
+            non_parsed_string: name.clone(),
+            semver: resolved.semver,
+        },
+        None => resolved,
+    };
+    let package = package.at_version(parsed_version.clone(), platform)?;
+
+    // An override may change the archive format along with its name.
+    let file_type = spec.for_version(&parsed_version).file_type(platform)?;
     let file_path = root.join(format!("{}.{}", parsed_version.tag_name, file_type));
     let download_url = download::download_url(&package, platform);
-    downloader.download(&download_url, &file_path).await?;
+    let version_dir = root.join(&parsed_version.tag_name);
+    // Offline there is no telling whether a moving tag was rebuilt, so it is
+    // treated like any other.
+    let stamp = match offline {
+        true => None,
+        false => {
+            moving_release_stamp(
+                &spec,
+                &parsed_version.tag_name,
+                &download_url,
+                release_provider,
+            )
+            .await?
+        }
+    };
+
+    if installed.is_some() {
+        if stamp.is_none() || installed_stamp(fs, &version_dir).await? == stamp {
+            info!("{} is already installed.", parsed_version.tag_name);
+            proxy.ensure_proxies(&package.binary_names()).await?;
+            return Ok(package);
+        }
+        info!(
+            "{} was rebuilt upstream, reinstalling it.",
+            parsed_version.tag_name
+        );
+    }
+
+    proxy.ensure_proxies(&package.binary_names()).await?;
+    // Checksum files live next to the release, so offline we rely on the
+    // digest recorded when the archive was cached.
+    let expected = match offline {
+        true => None,
+        false => {
+            expected_checksum(&spec, &parsed_version, &download_url, platform, downloader).await?
+        }
+    };
+
+    // The cached archive of a moving tag may be an older build.
+    let cached = match stamp {
+        Some(_) => None,
+        None => cache.get(&download_url, expected.as_deref()).await?,
+    };
+
+    match cached {
+        Some(cached) => {
+            info!("Using cached archive {}", cached.path.display());
+            fs.copy(&cached.path, &file_path).await?;
+        }
+        None if offline => {
+            return Err(anyhow!(
+                "{} {} is not in the download cache ({download_url}). Run without --offline to \
+                 download it.",
+                spec.id,
+                parsed_version.tag_name
+            ));
+        }
+        None => {
+            let digest = downloader.download(&download_url, &file_path).await?;
+
+            if let Some(expected) = expected {
+                if !expected.eq_ignore_ascii_case(&digest) {
+                    fs.remove_file(&file_path).await?;
+                    return Err(anyhow!(
+                        "Checksum mismatch for {download_url}: expected sha256 {expected}, got \
+                         {digest}. The archive was discarded without being extracted."
+                    ));
+                }
+                info!("Verified sha256 checksum {digest}");
+            }
+
+            cache.put(&download_url, &file_path, &digest).await?;
+        }
+    }
 
     let local_version = LocalVersion {
         file_name: parsed_version.tag_name.to_owned(),
@@ -72,22 +214,67 @@ where
         semver: parsed_version.semver.clone(),
     };
 
-    archive.extract(package, local_version).await?;
+    archive.extract(package.clone(), local_version).await?;
+
+    if let Some(stamp) = stamp {
+        let contents = serde_json::to_string(&stamp)?;
+        fs.write(&version_dir.join(RELEASE_STAMP_FILE), &contents).await?;
+    }
+
     info!("Successfully installed {}", parsed_version.tag_name);
 
-    Ok(())
+    Ok(package)
 }
 
-async fn version_exists(fs: &impl Fs, version: &str, downloads_dir: &Path) -> anyhow::Result<bool> {
-    let entries = fs.read_dir(downloads_dir).await?;
-    for entry in entries {
-        if !fs.is_dir(&entry).await? {
-            continue;
-        }
-        let name = entry.file_name().unwrap_or_default().to_string_lossy().to_string();
-        if name == version {
-            return Ok(true);
-        }
+/// The stamp of the build `download_url` currently points to when `tag` is a
+/// moving tag, `None` otherwise.
+pub async fn moving_release_stamp(
+    spec: &PackageSpec,
+    tag: &str,
+    download_url: &str,
+    release_provider: &impl ReleaseProvider,
+) -> anyhow::Result<Option<ReleaseStamp>> {
+    if !spec.is_moving_tag(tag) {
+        return Ok(None);
     }
-    Ok(false)
+
+    let release = release_provider.release(spec, tag).await?;
+    let file_name = download_url.rsplit('/').next().unwrap_or_default();
+    Ok(Some(ReleaseStamp::of(&release, file_name)))
+}
+
+/// The stamp recorded when the moving tag in `version_dir` was installed.
+pub async fn installed_stamp(
+    fs: &impl Fs,
+    version_dir: &Path,
+) -> anyhow::Result<Option<ReleaseStamp>> {
+    let path = version_dir.join(RELEASE_STAMP_FILE);
+    if !fs.exists(&path).await? {
+        return Ok(None);
+    }
+    Ok(serde_json::from_str(&fs.read_to_string(&path).await?).ok())
+}
+
+/// Fetches the checksum file declared by the spec and returns the digest
+/// listed for the release asset.
+async fn expected_checksum(
+    spec: &PackageSpec,
+    version: &ParsedVersion,
+    download_url: &str,
+    platform: &impl Platform,
+    downloader: &impl Downloader,
+) -> anyhow::Result<Option<String>> {
+    let Some(checksum_url) = spec.checksum_url(version, platform)? else {
+        return Ok(None);
+    };
+
+    let file_name = download_url.rsplit('/').next().unwrap_or_default();
+    let contents = downloader
+        .fetch(&checksum_url)
+        .await
+        .map_err(|err| anyhow!("Failed to fetch checksums from {checksum_url}: {err}"))?;
+
+    expected_digest(&contents, file_name)
+        .map(Some)
+        .ok_or_else(|| anyhow!("No sha256 checksum for {file_name} in {checksum_url}"))
 }
@@ -0,0 +1,199 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use crate::domain::package::Channel;
+use crate::domain::package::PackageRegistry;
+use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_version_req;
+use crate::domain::version::Version;
+use crate::ports::Fs;
+use crate::ports::RootDir;
+
+/// Returns the packages that have at least one version installed under the
+/// root dir, ordered by id.
+pub async fn installed_specs(
+    registry: &PackageRegistry,
+    root: &impl RootDir,
+    fs: &impl Fs,
+) -> anyhow::Result<Vec<Arc<PackageSpec>>> {
+    let root_dir = root.root_dir().await?;
+    let mut installed = Vec::new();
+
+    for spec in registry.specs() {
+        let package_dir = root_dir.join(&spec.alias);
+        if !fs.is_dir(&package_dir).await.unwrap_or(false) {
+            continue;
+        }
+
+        if !installed_versions(fs, &package_dir).await?.is_empty() {
+            installed.push(spec);
+        }
+    }
+
+    Ok(installed)
+}
+
+/// Returns the names of the version directories in a package's downloads dir.
+pub async fn installed_versions(fs: &impl Fs, downloads_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut versions = Vec::new();
+    for entry in fs.read_dir(downloads_dir).await? {
+        if fs.is_dir(&entry).await? {
+            versions.push(entry.file_name().unwrap_or_default().to_string_lossy().to_string());
+        }
+    }
+
+    Ok(versions)
+}
+
+/// The name of the installed version directory that stands for `version`,
+/// whatever its spelling, so `1.2.3` finds `v1.2.3`.
+pub async fn installed_dir(
+    spec: &PackageSpec,
+    fs: &impl Fs,
+    downloads_dir: &Path,
+    version: &str,
+) -> anyhow::Result<Option<String>> {
+    if !fs.is_dir(downloads_dir).await.unwrap_or(false) {
+        return Ok(None);
+    }
+    let installed = installed_versions(fs, downloads_dir).await?;
+    Ok(find_installed(spec, &installed, version))
+}
+
+/// The name among `installed` that stands for `version`, preferring the same
+/// spelling.
+pub fn find_installed(spec: &PackageSpec, installed: &[String], version: &str) -> Option<String> {
+    let wanted = spec.version(version);
+    installed
+        .iter()
+        .find(|name| *name == version)
+        .or_else(|| installed.iter().find(|name| spec.version(name) == wanted))
+        .cloned()
+}
+
+/// The installed version `requested` refers to: the same version under any
+/// spelling, the tag of a channel, or the newest one within a range or the
+/// range of a channel.
+pub fn installed_match(
+    spec: &PackageSpec,
+    requested: &str,
+    installed: &[String],
+) -> Option<String> {
+    let channel = spec.channels.get(requested);
+    let tag = match channel {
+        Some(Channel::Tag(tag)) => tag.as_str(),
+        _ => requested,
+    };
+    if let Some(version) = find_installed(spec, installed, tag) {
+        return Some(version);
+    }
+
+    let req = match channel {
+        Some(Channel::Newest { versions, .. }) => versions.clone(),
+        Some(Channel::Tag(_)) => None,
+        None => parse_version_req(requested),
+    }?;
+    installed
+        .iter()
+        .filter(|name| spec.version(name).semver().is_some_and(|semver| req.matches(semver)))
+        .max_by_key(|name| spec.version(name))
+        .cloned()
+}
+
+/// An installed version along with when it was installed and the disk space
+/// it takes.
+pub struct InstalledVersion {
+    pub name: String,
+    pub version: Version,
+    pub installed_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// Returns the installed versions of `spec`, newest first.
+pub async fn installed_details(
+    spec: &PackageSpec,
+    fs: &impl Fs,
+    downloads_dir: &Path,
+) -> anyhow::Result<Vec<InstalledVersion>> {
+    let mut installed = Vec::new();
+    for name in installed_versions(fs, downloads_dir).await? {
+        let path = downloads_dir.join(&name);
+        installed.push(InstalledVersion {
+            version: spec.version(&name),
+            installed_at: fs.modified(&path).await?.into(),
+            size: fs.dir_size(&path).await?,
+            name,
+        });
+    }
+
+    installed.sort_by(|a, b| b.version.cmp(&a.version));
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testing::tool_spec;
+
+    const INSTALLED: [&str; 6] = [
+        "v1.0.0",
+        "v1.0.3",
+        "v1.1.0",
+        "2.0.0",
+        "v2.1.0-rc.1",
+        "nightly",
+    ];
+
+    fn matched(requested: &str) -> Option<String> {
+        let spec = tool_spec(
+            r#"
+            [package.channels]
+            nightly = { tag = "nightly" }
+            edge = { tag = "edge" }
+            old = { versions = "~1.0" }
+            "#,
+        );
+        let installed: Vec<String> = INSTALLED.iter().map(|name| name.to_string()).collect();
+        installed_match(&spec, requested, &installed)
+    }
+
+    #[test]
+    fn exact_versions_match_under_any_spelling() {
+        assert_eq!(matched("v1.1.0").as_deref(), Some("v1.1.0"));
+        assert_eq!(matched("1.1.0").as_deref(), Some("v1.1.0"));
+        assert_eq!(matched("v2.0.0").as_deref(), Some("2.0.0"));
+        assert_eq!(matched("v1.2.0"), None);
+    }
+
+    #[test]
+    fn ranges_match_the_newest_installed_version_within_them() {
+        assert_eq!(matched("1").as_deref(), Some("v1.1.0"));
+        assert_eq!(matched("~1.0").as_deref(), Some("v1.0.3"));
+        assert_eq!(matched("^2").as_deref(), Some("2.0.0"));
+        assert_eq!(matched("^3"), None);
+    }
+
+    #[test]
+    fn channels_match_their_tag_or_their_range() {
+        assert_eq!(matched("nightly").as_deref(), Some("nightly"));
+        assert_eq!(matched("old").as_deref(), Some("v1.0.3"));
+        assert_eq!(matched("edge"), None);
+    }
+
+    #[test]
+    fn the_same_spelling_is_preferred() {
+        let spec = tool_spec("");
+        let installed = vec!["1.0.0".to_string(), "v1.0.0".to_string()];
+        assert_eq!(
+            find_installed(&spec, &installed, "v1.0.0").as_deref(),
+            Some("v1.0.0")
+        );
+        assert_eq!(
+            find_installed(&spec, &installed, "1.0.0").as_deref(),
+            Some("1.0.0")
+        );
+    }
+}
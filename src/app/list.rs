@@ -1,20 +1,27 @@
-use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::Error;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::Cell;
-use comfy_table::CellAlignment;
 use comfy_table::Color;
 use comfy_table::Table;
+use indicatif::HumanBytes;
 use serde::Serialize;
 use tracing::info;
+use tracing::warn;
 
+use crate::app::installed::installed_details;
 use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
 use crate::ports::Fs;
 use crate::ports::Output;
 use crate::ports::Paths;
+use crate::ports::Platform;
+use crate::ports::ReleaseProvider;
 use crate::ports::UsedVersionStore;
 use crate::OutputFormat;
 
@@ -22,71 +29,100 @@ use crate::OutputFormat;
 struct ListedVersion {
     version: String,
     status: String,
+    latest: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published_at: Option<DateTime<Utc>>,
+    installed_at: DateTime<Utc>,
+    size: u64,
 }
 
+/// Lists the installed versions of `package`, newest first. The latest
+/// release is marked when it can be looked up, and publish dates are only
+/// shown with `published`, as they take the whole release list. Offline, or
+/// when the lookups fail, the list is built from disk alone.
+#[allow(clippy::too_many_arguments)]
 pub async fn list_installed(
     package: Package,
     fmt: OutputFormat,
+    published: bool,
+    offline: bool,
+    provider: &impl ReleaseProvider,
     output: &impl Output,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
     fs: &impl Fs,
 ) -> Result<(), Error> {
+    let spec = package.spec();
     let downloads_dir = paths.downloads_dir(package.clone()).await?;
+    let installed = installed_details(spec, fs, &downloads_dir).await?;
 
-    let entries: Vec<PathBuf> = fs.read_dir(&downloads_dir).await?;
-
-    if entries.is_empty() {
+    if installed.is_empty() {
         info!("There are no versions installed");
         return Ok(());
     }
 
-    let current = used_store.current(package.clone()).await?;
-    let current_norm = current.as_deref().map(normalize_tag);
+    let latest = match offline {
+        true => None,
+        false => match provider.latest(spec).await {
+            Ok(latest) => Some(spec.version(&latest.tag_name)),
+            Err(err) => {
+                warn!("Could not look up the latest release of {}: {err}", spec.id);
+                None
+            }
+        },
+    };
+    let releases = match published && !offline {
+        true => match provider.list(spec).await {
+            Ok(releases) => releases,
+            Err(err) => {
+                warn!("Could not fetch the releases of {}: {err}", spec.id);
+                Vec::new()
+            }
+        },
+        false => Vec::new(),
+    };
+    let current = used_store.current(package.clone()).await?.map(|tag| spec.version(&tag));
+
+    let rows: Vec<ListedVersion> = installed
+        .into_iter()
+        .map(|local| ListedVersion {
+            status: match current.as_ref() == Some(&local.version) {
+                true => "used".to_string(),
+                false => "installed".to_string(),
+            },
+            latest: latest.as_ref() == Some(&local.version),
+            published_at: releases
+                .iter()
+                .find(|release| spec.version(&release.tag_name) == local.version)
+                .and_then(|release| release.published_at),
+            installed_at: local.installed_at,
+            size: local.size,
+            version: local.name,
+        })
+        .collect();
 
     match fmt {
-        OutputFormat::Json => {
-            let mut rows = Vec::new();
-            for path in entries {
-                if !fs.is_dir(&path).await? {
-                    continue;
-                }
-                let path_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
-                let path_norm = normalize_tag(&path_name);
-                let status = match current_norm {
-                    Some(cur) if cur == path_norm => "used",
-                    _ => "installed",
-                };
-                rows.push(ListedVersion {
-                    version: path_name,
-                    status: status.to_string(),
-                });
-            }
-            let json = serde_json::to_string_pretty(&rows)?;
-            output.write_line(&json)?;
-        }
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&rows)?)?,
         OutputFormat::Table => {
             let mut table = Table::new();
-            let header = vec!["Version", "Status"];
             table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+            let mut header = vec!["Version", "Status", "Installed", "Size"];
+            if published {
+                header.insert(2, "Published");
+            }
             table.set_header(header);
 
-            for path in entries {
-                if !fs.is_dir(&path).await? {
-                    continue;
+            for row in &rows {
+                let mut cells = vec![
+                    Cell::new(&row.version),
+                    status_cell(row.status == "used", true, row.latest),
+                    Cell::new(row.installed_at.format("%Y-%m-%d")),
+                    Cell::new(HumanBytes(row.size)),
+                ];
+                if published {
+                    cells.insert(2, Cell::new(date(row.published_at)));
                 }
-
-                let path_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
-                let path_norm = normalize_tag(&path_name);
-                let status = match current_norm {
-                    Some(cur) if cur == path_norm => Cell::new("Used").fg(Color::Green),
-                    _ => Cell::new("Installed"),
-                };
-
-                table.add_row(vec![
-                    Cell::new(path_name).set_alignment(CellAlignment::Center),
-                    status,
-                ]);
+                table.add_row(cells);
             }
 
             output.write_line(&table.to_string())?;
@@ -96,9 +132,112 @@ pub async fn list_installed(
     Ok(())
 }
 
-fn normalize_tag(tag: &str) -> &str {
-    match tag.strip_prefix('v') {
-        Some(rest) if rest.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false) => rest,
-        _ => tag,
+/// Labels a version as used, installed or neither, adding whether it is the
+/// latest release.
+pub fn status_cell(used: bool, installed: bool, latest: bool) -> Cell {
+    let (label, color) = match (used, installed) {
+        (true, _) => ("Used", Some(Color::Green)),
+        (false, true) => ("Installed", Some(Color::Yellow)),
+        (false, false) => ("", None),
+    };
+    let label = match (latest, label.is_empty()) {
+        (true, true) => "Latest".to_string(),
+        (true, false) => format!("{label}, latest"),
+        (false, _) => label.to_string(),
+    };
+
+    match color {
+        Some(color) => Cell::new(label).fg(color),
+        None => Cell::new(label),
     }
 }
+
+pub fn date(value: Option<DateTime<Utc>>) -> String {
+    value.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct InstalledPackage {
+    package: String,
+    used: Option<String>,
+    versions: Vec<InstalledVersion>,
+}
+
+#[derive(Serialize)]
+struct InstalledVersion {
+    version: String,
+    used: bool,
+    installed_at: DateTime<Utc>,
+    size: u64,
+}
+
+/// Lists every installed package with its versions, newest first, along with
+/// when each was installed and the disk space it takes.
+pub async fn list_all(
+    specs: Vec<Arc<PackageSpec>>,
+    fmt: OutputFormat,
+    output: &impl Output,
+    platform: &impl Platform,
+    paths: &impl Paths,
+    used_store: &impl UsedVersionStore,
+    fs: &impl Fs,
+) -> Result<(), Error> {
+    if specs.is_empty() {
+        info!("There are no packages installed");
+        return Ok(());
+    }
+
+    let mut packages = Vec::new();
+    for spec in specs {
+        let package = Package::from_spec(spec.clone(), platform)?;
+        let downloads_dir = paths.downloads_dir(package.clone()).await?;
+        let used = used_store.current(package).await?;
+
+        let current = used.as_deref().map(|tag| spec.version(tag));
+        let versions = installed_details(&spec, fs, &downloads_dir)
+            .await?
+            .into_iter()
+            .map(|local| InstalledVersion {
+                used: current.as_ref() == Some(&local.version),
+                installed_at: local.installed_at,
+                size: local.size,
+                version: local.name,
+            })
+            .collect();
+
+        packages.push(InstalledPackage {
+            package: spec.id.clone(),
+            used,
+            versions,
+        });
+    }
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&packages)?)?,
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+            table.set_header(vec!["Package", "Version", "Status", "Installed", "Size"]);
+
+            for package in &packages {
+                for version in &package.versions {
+                    let status = match version.used {
+                        true => Cell::new("Used").fg(Color::Green),
+                        false => Cell::new("Installed"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&package.package),
+                        Cell::new(&version.version),
+                        status,
+                        Cell::new(version.installed_at.format("%Y-%m-%d")),
+                        Cell::new(HumanBytes(version.size)),
+                    ]);
+                }
+            }
+
+            output.write_line(&table.to_string())?;
+        }
+    }
+
+    Ok(())
+}
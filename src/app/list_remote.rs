@@ -1,12 +1,25 @@
-use std::path::PathBuf;
+use std::cmp::Reverse;
 
 use anyhow::Error;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Cell;
+use comfy_table::Table;
+use indicatif::HumanBytes;
+use regex::Regex;
 use serde::Serialize;
-use yansi::Paint;
 
+use crate::app::installed::installed_details;
+use crate::app::installed::InstalledVersion;
+use crate::app::list::date;
+use crate::app::list::status_cell;
 use crate::domain::package::Package;
 use crate::domain::version::RemoteVersion;
+use crate::domain::version::Version;
 use crate::domain::version::VersionStatus;
 use crate::ports::Fs;
 use crate::ports::Output;
@@ -19,101 +32,155 @@ use crate::OutputFormat;
 struct RemoteEntry {
     version: String,
     status: String,
+    latest: bool,
+    prerelease: bool,
+    /// False for installed versions whose release is gone from the remote.
+    on_remote: bool,
+    published_at: Option<DateTime<Utc>>,
+    installed_at: Option<DateTime<Utc>>,
+    size: Option<u64>,
 }
 
+/// Narrows down the releases shown by `list-remote`.
+#[derive(Default)]
+pub struct RemoteFilter {
+    pub limit: Option<usize>,
+    pub include_prereleases: bool,
+    pub since: Option<NaiveDate>,
+    pub tag: Option<Regex>,
+}
+
+/// Lists the remote releases of `package` newest first, marking the latest
+/// one and the ones installed, then the installed versions whose release is
+/// no longer on the remote.
+#[allow(clippy::too_many_arguments)]
 pub async fn list_remote(
     package: Package,
     fmt: OutputFormat,
+    filter: &RemoteFilter,
     provider: &impl ReleaseProvider,
     output: &impl Output,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
     fs: &impl Fs,
 ) -> Result<(), Error> {
-    let versions = provider.list(package.spec()).await?;
-    let downloads_dir = paths.downloads_dir(package.clone()).await?;
-    let local_versions: Vec<PathBuf> = filter_local_versions(fs, downloads_dir).await?;
-    let filtered_versions: Vec<RemoteVersion> = filter_versions(versions)?;
+    let spec = package.spec();
+    let mut releases = provider.list(spec).await?;
+    releases.sort_by_cached_key(|release| Reverse(spec.version(&release.tag_name)));
+    let latest = spec
+        .newest_release(releases.clone(), None)
+        .map(|latest| spec.version(&latest.tag_name));
 
-    let padding = " ".repeat(12);
-    let mut json_entries = Vec::new();
-    let current = used_store.current(package.clone()).await?;
-    let current_norm = current.as_deref().map(normalize_tag);
-    for version in filtered_versions {
-        let version_installed = check_version_installed(&local_versions, &version.tag_name);
-        let tag = version.tag_name.clone();
+    let downloads_dir = paths.downloads_dir(package.clone()).await?;
+    let installed = installed_details(spec, fs, &downloads_dir).await.unwrap_or_default();
+    let current = used_store.current(package.clone()).await?.map(|tag| spec.version(&tag));
+    let status = |version: &Version, local: Option<&InstalledVersion>| match (
+        current.as_ref() == Some(version),
+        local.is_some(),
+    ) {
+        (true, _) => VersionStatus::Used,
+        (false, true) => VersionStatus::Installed,
+        (false, false) => VersionStatus::NotInstalled,
+    };
 
-        let tag_norm = normalize_tag(&tag);
-        let version_status = match current_norm {
-            Some(current) if current == tag_norm => VersionStatus::Used,
-            _ if version_installed => VersionStatus::Installed,
-            _ => VersionStatus::NotInstalled,
-        };
+    let remote_versions: Vec<Version> =
+        releases.iter().map(|r| spec.version(&r.tag_name)).collect();
+    let gone: Vec<RemoteEntry> = installed
+        .iter()
+        .filter(|local| !remote_versions.contains(&local.version))
+        .map(|local| RemoteEntry {
+            version: local.name.clone(),
+            status: status_name(&status(&local.version, Some(local))).to_string(),
+            latest: false,
+            prerelease: false,
+            on_remote: false,
+            published_at: None,
+            installed_at: Some(local.installed_at),
+            size: Some(local.size),
+        })
+        .collect();
 
-        match fmt {
-            OutputFormat::Table => {
-                let line = match version_status {
-                    VersionStatus::Used => format!("{padding}{}", Paint::green(&tag)),
-                    VersionStatus::Installed => {
-                        retain_local_versions(local_versions.clone(), &version.tag_name);
-                        format!("{padding}{}", Paint::yellow(&tag))
-                    }
-                    VersionStatus::NotInstalled => format!("{padding}{}", Paint::italic(&tag)),
-                };
-                output.write_line(&line)?;
+    let listed: Vec<RemoteEntry> = filter_versions(releases, filter)?
+        .into_iter()
+        .map(|release| {
+            let version = spec.version(&release.tag_name);
+            let local = installed.iter().find(|local| local.version == version);
+            RemoteEntry {
+                status: status_name(&status(&version, local)).to_string(),
+                latest: latest.as_ref() == Some(&version),
+                prerelease: release.prerelease,
+                on_remote: true,
+                published_at: release.published_at,
+                installed_at: local.map(|local| local.installed_at),
+                size: local.map(|local| local.size),
+                version: release.tag_name,
             }
-            OutputFormat::Json => {
-                let status_str = match version_status {
-                    VersionStatus::Used => "used",
-                    VersionStatus::Installed => "installed",
-                    VersionStatus::NotInstalled => "not_installed",
-                };
-                json_entries.push(RemoteEntry {
-                    version: tag.clone(),
-                    status: status_str.to_string(),
-                });
+        })
+        .collect();
+
+    match fmt {
+        OutputFormat::Json => {
+            let entries: Vec<&RemoteEntry> = listed.iter().chain(&gone).collect();
+            output.write_line(&serde_json::to_string_pretty(&entries)?)?;
+        }
+        OutputFormat::Table => {
+            output.write_line(&table(&listed).to_string())?;
+            if !gone.is_empty() {
+                output.write_line("Installed locally but no longer on the remote:")?;
+                output.write_line(&table(&gone).to_string())?;
             }
         }
     }
 
-    if matches!(fmt, OutputFormat::Json) {
-        let json = serde_json::to_string_pretty(&json_entries)?;
-        output.write_line(&json)?;
-    }
-
     Ok(())
 }
 
-fn check_version_installed(local_versions: &[PathBuf], tag: &str) -> bool {
-    let tag_norm = normalize_tag(tag);
-    local_versions.iter().any(|v| {
-        v.file_name()
-            .and_then(|str| str.to_str())
-            .is_some_and(|str| normalize_tag(str) == tag_norm)
-    })
+fn table(entries: &[RemoteEntry]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+    table.set_header(vec!["Version", "Status", "Published", "Installed", "Size"]);
+    for entry in entries {
+        table.add_row(vec![
+            Cell::new(&entry.version),
+            status_cell(
+                entry.status == "used",
+                entry.status != "not_installed",
+                entry.latest,
+            ),
+            Cell::new(date(entry.published_at)),
+            Cell::new(date(entry.installed_at)),
+            Cell::new(entry.size.map(|size| HumanBytes(size).to_string()).unwrap_or_default()),
+        ]);
+    }
+    table
 }
 
-fn retain_local_versions(local_versions: Vec<PathBuf>, tag: &str) {
-    let mut local_versions = local_versions;
-    local_versions.retain(|v| {
-        v.file_name().and_then(|str| str.to_str()).is_none_or(|str| !str.contains(tag))
-    });
+fn status_name(status: &VersionStatus) -> &'static str {
+    match status {
+        VersionStatus::Used => "used",
+        VersionStatus::Installed => "installed",
+        VersionStatus::NotInstalled => "not_installed",
+    }
 }
 
-fn filter_versions(versions: Vec<RemoteVersion>) -> Result<Vec<RemoteVersion>, Error> {
-    Ok(versions.into_iter().filter(|v| !v.prerelease).collect())
-}
+fn filter_versions(
+    versions: Vec<RemoteVersion>,
+    filter: &RemoteFilter,
+) -> Result<Vec<RemoteVersion>, Error> {
+    let since = filter
+        .since
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc());
 
-async fn filter_local_versions(
-    fs: &impl Fs,
-    downloads_dir: PathBuf,
-) -> Result<Vec<PathBuf>, Error> {
-    fs.read_dir(&downloads_dir).await
-}
-
-fn normalize_tag(tag: &str) -> &str {
-    match tag.strip_prefix('v') {
-        Some(rest) if rest.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false) => rest,
-        _ => tag,
-    }
+    Ok(versions
+        .into_iter()
+        .filter(|v| filter.include_prereleases || !v.prerelease)
+        .filter(|v| match (since, v.published_at) {
+            (Some(since), Some(published)) => published >= since,
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .filter(|v| filter.tag.as_ref().is_none_or(|re| re.is_match(&v.tag_name)))
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect())
 }
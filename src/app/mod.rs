@@ -1,8 +1,20 @@
+pub mod cache;
 pub mod download;
 pub mod erase;
+pub mod exec;
+pub mod info;
 pub mod install;
+pub mod installed;
 pub mod list;
 pub mod list_remote;
+pub mod outdated;
+pub mod pin;
 pub mod proxy;
+pub mod prune;
+pub mod registry;
+pub mod reshim;
 pub mod resolve;
+#[cfg(test)]
+pub mod testing;
 pub mod uninstall;
+pub mod upgrade;
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Cell;
+use comfy_table::Color;
+use comfy_table::Table;
+use futures_util::future::join_all;
+use serde::Serialize;
+use tracing::error;
+
+use crate::app::download;
+use crate::app::install::installed_stamp;
+use crate::app::install::moving_release_stamp;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_normal_version;
+use crate::ports::Fs;
+use crate::ports::Output;
+use crate::ports::Paths;
+use crate::ports::Platform;
+use crate::ports::ReleaseProvider;
+use crate::ports::UsedVersionStore;
+use crate::OutputFormat;
+
+#[derive(Serialize)]
+struct OutdatedEntry {
+    package: String,
+    current: Option<String>,
+    latest: String,
+    behind: String,
+    outdated: bool,
+}
+
+/// Compares the used version of each package with its latest release, or
+/// with the current build of its tag when it uses a moving tag like `nightly`.
+///
+/// Fails when any package is behind or its latest release could not be
+/// looked up, so it can gate scripts and CI jobs.
+#[allow(clippy::too_many_arguments)]
+pub async fn outdated(
+    specs: Vec<Arc<PackageSpec>>,
+    fmt: OutputFormat,
+    provider: &impl ReleaseProvider,
+    output: &impl Output,
+    platform: &impl Platform,
+    used_store: &impl UsedVersionStore,
+    paths: &impl Paths,
+    fs: &impl Fs,
+) -> Result<()> {
+    let lookups = specs.iter().map(|spec| async move {
+        let package = Package::from_spec(spec.clone(), platform)?;
+        let current = used_store.current(package.clone()).await?;
+        if let Some(tag) = current.as_deref().filter(|tag| spec.is_moving_tag(tag)) {
+            let package = package.at_version(parse_normal_version(tag).await?, platform)?;
+            let download_url = download::download_url(&package, platform);
+            let version_dir = paths.downloads_dir(package).await?.join(tag);
+            let remote = moving_release_stamp(spec, tag, &download_url, provider).await?;
+            let rebuilt = installed_stamp(fs, &version_dir).await? != remote;
+            return Ok(OutdatedEntry {
+                package: spec.id.clone(),
+                current: Some(tag.to_string()),
+                latest: tag.to_string(),
+                behind: match rebuilt {
+                    true => "rebuilt".to_string(),
+                    false => "up to date".to_string(),
+                },
+                outdated: rebuilt,
+            });
+        }
+        let latest = provider.latest(spec).await?;
+        Ok::<_, anyhow::Error>(entry(spec, current, latest.tag_name))
+    });
+
+    let mut entries = Vec::new();
+    let mut failed = Vec::new();
+    for (spec, result) in specs.iter().zip(join_all(lookups).await) {
+        match result {
+            Ok(entry) => entries.push(entry),
+            Err(err) => {
+                error!("Failed to check {}: {err}", spec.id);
+                failed.push(spec.id.clone());
+            }
+        }
+    }
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&entries)?)?,
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+            table.set_header(vec!["Package", "Current", "Latest", "Behind"]);
+            for entry in &entries {
+                let behind = match entry.outdated {
+                    true => Cell::new(&entry.behind).fg(Color::Yellow),
+                    false => Cell::new(&entry.behind).fg(Color::Green),
+                };
+                table.add_row(vec![
+                    Cell::new(&entry.package),
+                    Cell::new(entry.current.as_deref().unwrap_or("-")),
+                    Cell::new(&entry.latest),
+                    behind,
+                ]);
+            }
+            output.write_line(&table.to_string())?;
+        }
+    }
+
+    let outdated = entries.iter().filter(|entry| entry.outdated).count();
+    match (outdated, failed.is_empty()) {
+        (0, true) => Ok(()),
+        (0, false) => Err(anyhow!("Failed to check {}", failed.join(", "))),
+        (n, _) => Err(anyhow!("{n} of {} packages are out of date", specs.len())),
+    }
+}
+
+fn entry(spec: &PackageSpec, current: Option<String>, latest: String) -> OutdatedEntry {
+    let (outdated, behind) = match current.as_deref() {
+        None => (false, "not in use".to_string()),
+        Some(current) => behind(spec, current, &latest),
+    };
+
+    OutdatedEntry {
+        package: spec.id.clone(),
+        current,
+        latest,
+        behind,
+        outdated,
+    }
+}
+
+/// Describes how far `current` trails `latest` by the most significant
+/// version component that differs.
+fn behind(spec: &PackageSpec, current: &str, latest: &str) -> (bool, String) {
+    let (current, latest) = (spec.version(current), spec.version(latest));
+    if current.is_at_least(&latest) {
+        return (false, "up to date".to_string());
+    }
+    let (Some(current), Some(latest)) = (current.semver(), latest.semver()) else {
+        return match current.is_numbered() && latest.is_numbered() {
+            true => (true, "behind".to_string()),
+            false => (true, "differs".to_string()),
+        };
+    };
+
+    let behind = if latest.major > current.major {
+        format!("{} major", latest.major - current.major)
+    } else if latest.minor > current.minor {
+        format!("{} minor", latest.minor - current.minor)
+    } else if latest.patch > current.patch {
+        format!("{} patch", latest.patch - current.patch)
+    } else {
+        "prerelease".to_string()
+    };
+
+    (true, behind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testing::tool_spec;
+
+    fn behind(current: &str, latest: &str) -> (bool, String) {
+        super::behind(&tool_spec(""), current, latest)
+    }
+
+    #[test]
+    fn behind_counts_the_most_significant_component() {
+        assert_eq!(behind("v1.2.3", "v3.0.0"), (true, "2 major".to_string()));
+        assert_eq!(behind("v1.2.3", "v1.5.0"), (true, "3 minor".to_string()));
+        assert_eq!(behind("v1.2.3", "v1.2.4"), (true, "1 patch".to_string()));
+        assert_eq!(
+            behind("v1.2.3-rc.1", "v1.2.3"),
+            (true, "prerelease".to_string())
+        );
+    }
+
+    #[test]
+    fn current_or_newer_versions_are_up_to_date() {
+        assert_eq!(
+            behind("v1.2.3", "v1.2.3"),
+            (false, "up to date".to_string())
+        );
+        assert_eq!(behind("1.2.3", "v1.2.3"), (false, "up to date".to_string()));
+        assert_eq!(
+            behind("v1.3.0", "v1.2.3"),
+            (false, "up to date".to_string())
+        );
+    }
+
+    #[test]
+    fn versions_without_semver_are_compared_loosely() {
+        assert_eq!(
+            behind("2024.01.15", "2024.02.01"),
+            (true, "behind".to_string())
+        );
+        assert_eq!(behind("nightly", "v1.3.0"), (true, "differs".to_string()));
+    }
+
+    #[test]
+    fn unused_packages_are_not_outdated() {
+        let entry = entry(&tool_spec(""), None, "v1.0.0".to_string());
+        assert!(!entry.outdated);
+        assert_eq!(entry.behind, "not in use");
+    }
+}
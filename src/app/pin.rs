@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::app::installed::installed_match;
+use crate::app::installed::installed_versions;
+use crate::app::resolve::resolve_requested_version;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_normal_version;
+use crate::ports::Fs;
+use crate::ports::Paths;
+use crate::ports::PinStore;
+use crate::ports::Platform;
+use crate::ports::ReleaseProvider;
+
+/// Pins `requested_version` of a package for the current project. Ranges,
+/// channels and `latest` are written as a concrete tag: the installed one they
+/// match, or else the release they resolve to.
+#[allow(clippy::too_many_arguments)]
+pub async fn pin(
+    spec: Arc<PackageSpec>,
+    requested_version: String,
+    release_provider: &impl ReleaseProvider,
+    platform: &impl Platform,
+    fs: &impl Fs,
+    paths: &impl Paths,
+    pins: &impl PinStore,
+) -> anyhow::Result<()> {
+    let downloads_dir = paths.downloads_dir(Package::from_spec(spec.clone(), platform)?).await?;
+    let installed = installed_versions(fs, &downloads_dir).await.unwrap_or_default();
+    let parsed = match installed_match(&spec, &requested_version, &installed) {
+        Some(version) => parse_normal_version(&version).await?,
+        None => {
+            resolve_requested_version(&requested_version, &spec, &installed, release_provider)
+                .await?
+        }
+    };
+    let package = Package::with_parsed(spec, parsed.clone(), platform)?;
+    let file = pins.pin(package.clone(), &parsed.tag_name).await?;
+
+    info!(
+        "Pinned {} to {} in {}",
+        package.alias(),
+        parsed.tag_name,
+        file.display()
+    );
+
+    Ok(())
+}
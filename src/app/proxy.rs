@@ -1,14 +1,21 @@
+use std::path::Path;
+
 use anyhow::anyhow;
 use anyhow::Result;
 
+use crate::app::installed::installed_dir;
 use crate::domain::package::Package;
 use crate::domain::package::PackageRegistry;
+use crate::domain::version::parse_normal_version;
+use crate::ports::Fs;
 use crate::ports::Output;
 use crate::ports::Paths;
+use crate::ports::PinStore;
 use crate::ports::Platform;
 use crate::ports::Process;
 use crate::ports::UsedVersionStore;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_proxy(
     exec_name: &str,
     rest_args: &[String],
@@ -16,6 +23,8 @@ pub async fn handle_proxy(
     output: &impl Output,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
+    pins: &impl PinStore,
+    fs: &impl Fs,
     platform: &impl Platform,
     process: &impl Process,
 ) -> miette::Result<()> {
@@ -30,33 +39,97 @@ pub async fn handle_proxy(
         return Ok(());
     }
 
-    let spec = registry.get_by_alias(exec_name).map_err(|err| miette::miette!(err))?;
-    let package = Package::from_spec(spec, platform).map_err(|err| miette::miette!(err))?;
+    let spec = registry.get_by_binary(exec_name).map_err(|err| miette::miette!(err))?;
+    let package = Package::from_spec(spec, platform)
+        .map_err(|err| miette::miette!(err))?
+        .with_binary(exec_name);
 
-    handle_package_process(rest_args, package, paths, used_store, process)
-        .await
-        .map_err(|err| miette::miette!("{err}"))?;
+    handle_package_process(
+        rest_args, package, None, paths, used_store, pins, fs, platform, process,
+    )
+    .await
+    .map_err(|err| miette::miette!("{err}"))?;
 
     Ok(())
 }
 
+/// Runs the package binary for `version` when given, otherwise for the
+/// version pinned by the nearest project file, falling back to the globally
+/// used version.
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_package_process(
     args: &[String],
     package: Package,
+    version: Option<String>,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
+    pins: &impl PinStore,
+    fs: &impl Fs,
+    platform: &impl Platform,
     process: &impl Process,
 ) -> Result<()> {
     let downloads_dir = paths.downloads_dir(package.clone()).await?;
-    let used_version = used_store
-        .current(package.clone())
-        .await?
-        .ok_or_else(|| anyhow!("No version in use for {}", package.alias()))?;
+    let spec = package.spec();
+    let version = match version {
+        Some(version) => {
+            installed_dir(spec, fs, &downloads_dir, &version).await?.ok_or_else(|| {
+                anyhow!(
+                    "{} {version} is not installed. Run `hj install {} {version}`",
+                    package.alias(),
+                    spec.id,
+                )
+            })?
+        }
+        None => match pins.pinned(package.clone()).await? {
+            Some(pin) => {
+                installed_dir(spec, fs, &downloads_dir, &pin.version).await?.ok_or_else(|| {
+                    anyhow!(
+                        "{} {} is pinned in {} but not installed. Run `hj install {} {}`",
+                        package.alias(),
+                        pin.version,
+                        pin.file.display(),
+                        spec.id,
+                        pin.version
+                    )
+                })?
+            }
+            None => match used_store.current(package.clone()).await? {
+                Some(version) => version,
+                None => return Err(not_in_use(&package, &downloads_dir, fs).await?),
+            },
+        },
+    };
 
+    // The binary path may depend on the version through an asset override.
+    let package = package.at_version(parse_normal_version(&version).await?, platform)?;
     let location = downloads_dir
-        .join(used_version)
+        .join(version)
         .join(package.binary_path())
         .join(package.binary_name());
 
     process.run(&location, args).await
 }
+
+/// The error for a package without a used version. Binaries that used to be
+/// packages of their own, like `cardano-cli`, may still have installs in a
+/// directory named after them, which nothing reads anymore.
+async fn not_in_use(
+    package: &Package,
+    downloads_dir: &Path,
+    fs: &impl Fs,
+) -> Result<anyhow::Error> {
+    let binary = package.binary_name();
+    let legacy_dir = downloads_dir.with_file_name(&binary);
+    if binary != package.alias() && fs.is_dir(&legacy_dir).await? {
+        return Ok(anyhow!(
+            "No version in use for {alias}. {binary} now ships with {alias}, the versions in {} \
+             belong to the former {binary} package. Run `hj use {} <version>` and remove that \
+             directory",
+            legacy_dir.display(),
+            package.spec().id,
+            alias = package.alias(),
+        ));
+    }
+
+    Ok(anyhow!("No version in use for {}", package.alias()))
+}
@@ -0,0 +1,223 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use indicatif::HumanBytes;
+use tracing::info;
+
+use crate::app::installed::installed_versions;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::ports::Fs;
+use crate::ports::Paths;
+use crate::ports::PinStore;
+use crate::ports::Platform;
+use crate::ports::UsedVersionStore;
+
+/// Which installed versions to keep. A version is pruned only when it falls
+/// outside every rule that is set.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Keep this many of the newest versions.
+    pub keep: Option<usize>,
+    /// Keep versions installed more recently than this.
+    pub older_than: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Whether to keep the version at `index` among the installed ones,
+    /// newest first, which was installed `age` ago.
+    pub fn retains(&self, index: usize, age: Duration) -> bool {
+        let within_keep = self.keep.is_some_and(|keep| index < keep);
+        let recent = self.older_than.is_some_and(|older_than| age <= older_than);
+        within_keep || recent
+    }
+}
+
+/// Removes the installed versions of `specs` that the policy does not retain.
+/// The used version and the versions pinned by any of `pins`, one per project,
+/// are always kept. With `dry_run` nothing is deleted.
+#[allow(clippy::too_many_arguments)]
+pub async fn prune(
+    specs: Vec<Arc<PackageSpec>>,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+    platform: &impl Platform,
+    fs: &impl Fs,
+    paths: &impl Paths,
+    used_store: &impl UsedVersionStore,
+    pins: &[impl PinStore],
+) -> Result<()> {
+    let now = SystemTime::now();
+    let mut freed = 0;
+    let mut removed = 0;
+
+    for spec in specs {
+        let package = Package::from_spec(spec.clone(), platform)?;
+        let downloads_dir = paths.downloads_dir(package.clone()).await?;
+        let used = used_store.current(package.clone()).await?.map(|tag| spec.version(&tag));
+        let mut pinned = Vec::new();
+        for store in pins {
+            if let Some(pin) = store.pinned(package.clone()).await? {
+                pinned.push(spec.version(&pin.version));
+            }
+        }
+
+        let mut versions = installed_versions(fs, &downloads_dir).await?;
+        versions.sort_by_cached_key(|version| Reverse(spec.version(version)));
+
+        for (index, version) in versions.iter().enumerate() {
+            let path = downloads_dir.join(version);
+            let age = match policy.older_than {
+                Some(_) => now.duration_since(fs.modified(&path).await?).unwrap_or_default(),
+                None => Duration::ZERO,
+            };
+            if policy.retains(index, age) {
+                continue;
+            }
+
+            if used.as_ref() == Some(&spec.version(version)) {
+                info!("Keeping {} {version}, it is in use", spec.id);
+                continue;
+            }
+            if pinned.contains(&spec.version(version)) {
+                info!("Keeping {} {version}, it is pinned", spec.id);
+                continue;
+            }
+
+            let size = fs.dir_size(&path).await?;
+            if dry_run {
+                info!("Would remove {} {version} ({})", spec.id, HumanBytes(size));
+            } else {
+                fs.remove_dir_all(&path).await?;
+                info!("Removed {} {version} ({})", spec.id, HumanBytes(size));
+            }
+            freed += size;
+            removed += 1;
+        }
+    }
+
+    match (removed, dry_run) {
+        (0, _) => info!("Nothing to prune"),
+        (_, true) => info!(
+            "Would remove {removed} versions, freeing {}",
+            HumanBytes(freed)
+        ),
+        (_, false) => info!("Removed {removed} versions, freed {}", HumanBytes(freed)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::adapters::fs::TokioFs;
+    use crate::adapters::path::FsPaths;
+    use crate::adapters::pin_store::ProjectPinStore;
+    use crate::adapters::used_store::UsedFileStore;
+    use crate::app::testing::tool_spec;
+    use crate::domain::platform::TargetPlatform;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hj-prune-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keep_retains_the_newest_versions() {
+        let policy = RetentionPolicy {
+            keep: Some(2),
+            older_than: None,
+        };
+        assert!(policy.retains(0, DAY * 100));
+        assert!(policy.retains(1, DAY * 100));
+        assert!(!policy.retains(2, Duration::ZERO));
+    }
+
+    #[test]
+    fn older_than_retains_recent_installs() {
+        let policy = RetentionPolicy {
+            keep: None,
+            older_than: Some(DAY * 30),
+        };
+        assert!(policy.retains(5, DAY));
+        assert!(policy.retains(5, DAY * 30));
+        assert!(!policy.retains(0, DAY * 31));
+    }
+
+    #[test]
+    fn versions_are_retained_by_any_rule() {
+        let policy = RetentionPolicy {
+            keep: Some(1),
+            older_than: Some(DAY * 30),
+        };
+        assert!(policy.retains(0, DAY * 100));
+        assert!(policy.retains(3, DAY));
+        assert!(!policy.retains(1, DAY * 31));
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_the_used_and_pinned_versions() {
+        let root = scratch_dir("prune");
+        let platform = TargetPlatform {
+            os: "linux",
+            arch: "x86_64",
+        };
+        let spec = tool_spec("");
+        let paths = FsPaths::new(root.join("data"));
+        let package = Package::from_spec(spec.clone(), &platform).unwrap();
+        let downloads_dir = paths.downloads_dir(package.clone()).await.unwrap();
+        let old = SystemTime::now() - DAY * 60;
+        for version in ["v1.0.0", "v1.1.0", "v1.2.0", "v2.0.0", "v2.1.0"] {
+            std::fs::create_dir_all(downloads_dir.join(version)).unwrap();
+            std::fs::File::open(downloads_dir.join(version))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        // Installed just now, so only `--keep` can remove it.
+        std::fs::create_dir_all(downloads_dir.join("v0.9.0")).unwrap();
+
+        let used_store = UsedFileStore::new(paths.clone());
+        used_store.set_current(package.clone(), "v1.0.0").await.unwrap();
+        let project = root.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join(".hj-versions"), "tool 1.1.0\n").unwrap();
+        let pins = [ProjectPinStore::new(project)];
+
+        let policy = RetentionPolicy {
+            keep: Some(1),
+            older_than: Some(DAY * 30),
+        };
+        let prune = |dry_run| {
+            prune(
+                vec![spec.clone()],
+                &policy,
+                dry_run,
+                &platform,
+                &TokioFs,
+                &paths,
+                &used_store,
+                &pins,
+            )
+        };
+
+        prune(true).await.unwrap();
+        let mut remaining = installed_versions(&TokioFs, &downloads_dir).await.unwrap();
+        assert_eq!(remaining.len(), 6);
+
+        prune(false).await.unwrap();
+        remaining = installed_versions(&TokioFs, &downloads_dir).await.unwrap();
+        remaining.sort();
+        assert_eq!(remaining, ["v0.9.0", "v1.0.0", "v1.1.0", "v2.1.0"]);
+    }
+}
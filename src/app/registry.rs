@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Cell;
+use comfy_table::Color;
+use comfy_table::Table;
+use futures_util::future::join_all;
+use regex::Regex;
+use serde::Serialize;
+use tracing::error;
+use tracing::info;
+
+use crate::domain::package::PackageRegistry;
+use crate::domain::package::PackageSpec;
+use crate::domain::package::RegistryLayer;
+use crate::domain::platform::TargetPlatform;
+use crate::domain::version::ParsedVersion;
+use crate::ports::Downloader;
+use crate::ports::Output;
+use crate::ports::RegistrySources;
+use crate::ports::ReleaseProvider;
+use crate::ports::SourceRefresh;
+use crate::OutputFormat;
+
+/// Builds the registry from `layers`, placing right after each one the
+/// `[[source]]` registries it lists. Sources listed by a fetched registry are
+/// not followed, and those of a project file are only read from the cache.
+pub async fn load_registry(
+    layers: Vec<RegistryLayer>,
+    sources: &impl RegistrySources,
+    refresh: SourceRefresh,
+) -> Result<PackageRegistry> {
+    let mut resolved = Vec::new();
+    for layer in layers {
+        let listed = layer.sources()?;
+        let refresh = match layer.project {
+            true => SourceRefresh::CacheOnly,
+            false => refresh,
+        };
+        resolved.push(layer);
+        for source in listed {
+            resolved.extend(sources.load(&source, refresh).await?);
+        }
+    }
+
+    PackageRegistry::load_layers(resolved)
+}
+
+/// Fetches every `[[source]]` listed in `layers` regardless of its TTL and
+/// checks that the registry still loads with them. Fails if any source could
+/// not be fetched.
+pub async fn update_sources(
+    layers: Vec<RegistryLayer>,
+    sources: &impl RegistrySources,
+) -> Result<()> {
+    let mut listed = Vec::new();
+    for layer in &layers {
+        listed.extend(layer.sources()?);
+    }
+    if listed.is_empty() {
+        info!("No registry sources configured");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for source in &listed {
+        if let Err(err) = sources.load(source, SourceRefresh::Always).await {
+            error!("{err:#}");
+            failed += 1;
+        }
+    }
+
+    let registry = load_registry(layers, sources, SourceRefresh::CacheOnly).await?;
+    if failed > 0 {
+        return Err(anyhow!(
+            "{failed} of {} registry sources could not be updated",
+            listed.len()
+        ));
+    }
+
+    info!(
+        "Updated {} registry sources, {} packages available",
+        listed.len(),
+        registry.specs().len()
+    );
+    Ok(())
+}
+
+/// Which checks `check_registry` runs and for which platforms.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Only lint the templates, without network access.
+    pub lint: bool,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    package: String,
+    target: Option<String>,
+    url: Option<String>,
+    ok: bool,
+    status: String,
+}
+
+impl CheckResult {
+    fn problem(spec: &PackageSpec, target: Option<&TargetPlatform>, status: String) -> Self {
+        Self {
+            package: spec.id.clone(),
+            target: target.map(label),
+            url: None,
+            ok: false,
+            status,
+        }
+    }
+}
+
+/// Lints every spec and, unless `options.lint` is set, sends a `HEAD` request
+/// for the download URL of its latest release on each mapped platform.
+/// Fails if any asset is missing or any spec has problems.
+pub async fn check_registry(
+    specs: Vec<Arc<PackageSpec>>,
+    options: &CheckOptions,
+    fmt: OutputFormat,
+    provider: &impl ReleaseProvider,
+    downloader: &impl Downloader,
+    output: &impl Output,
+) -> Result<()> {
+    let checks = specs.iter().map(|spec| check_spec(spec, options, provider, downloader));
+    let results: Vec<CheckResult> = join_all(checks).await.into_iter().flatten().collect();
+
+    match fmt {
+        OutputFormat::Json => output.write_line(&serde_json::to_string_pretty(&results)?)?,
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL).apply_modifier(UTF8_ROUND_CORNERS);
+            table.set_header(vec!["Package", "Platform", "Status", "Url"]);
+            for result in &results {
+                let color = if result.ok { Color::Green } else { Color::Red };
+                table.add_row(vec![
+                    Cell::new(&result.package),
+                    Cell::new(result.target.as_deref().unwrap_or("-")),
+                    Cell::new(&result.status).fg(color),
+                    Cell::new(result.url.as_deref().unwrap_or("")),
+                ]);
+            }
+            output.write_line(&table.to_string())?;
+        }
+    }
+
+    match results.iter().filter(|result| !result.ok).count() {
+        0 => Ok(()),
+        failed => Err(anyhow!("{failed} registry checks failed")),
+    }
+}
+
+async fn check_spec(
+    spec: &PackageSpec,
+    options: &CheckOptions,
+    provider: &impl ReleaseProvider,
+    downloader: &impl Downloader,
+) -> Vec<CheckResult> {
+    let targets = selected_targets(spec, options);
+
+    // The spec itself stands for the releases no override matches.
+    let base = PackageSpec {
+        overrides: Vec::new(),
+        ..spec.clone()
+    };
+    let mut results: Vec<CheckResult> = lint(&base, &targets)
+        .into_iter()
+        .map(|(target, problem)| CheckResult::problem(spec, target.as_ref(), problem))
+        .collect();
+    for entry in &spec.overrides {
+        let variant = spec.with_override(entry);
+        let problems = lint(&variant, &selected_targets(&variant, options));
+        results.extend(problems.into_iter().map(|(target, problem)| {
+            let problem = format!("{problem} (override for {})", entry.versions);
+            CheckResult::problem(spec, target.as_ref(), problem)
+        }));
+    }
+
+    if options.lint {
+        if results.is_empty() {
+            results.push(CheckResult {
+                package: spec.id.clone(),
+                target: None,
+                url: None,
+                ok: true,
+                status: "ok".to_string(),
+            });
+        }
+        return results;
+    }
+
+    let version = match provider.latest(spec).await {
+        Ok(version) => version,
+        Err(err) => {
+            results.push(CheckResult::problem(
+                spec,
+                None,
+                format!("latest release: {err}"),
+            ));
+            return results;
+        }
+    };
+
+    let probes = targets.iter().filter_map(|target| {
+        let url = spec.download_url(&version, target).ok()?;
+        Some(async move {
+            let status = downloader.exists(&url).await;
+            (target, url, status)
+        })
+    });
+
+    for (target, url, status) in join_all(probes).await {
+        let (ok, status) = match status {
+            Ok(true) => (true, format!("ok ({})", version.tag_name)),
+            Ok(false) => (false, format!("missing ({})", version.tag_name)),
+            Err(err) => (false, err.to_string()),
+        };
+        results.push(CheckResult {
+            package: spec.id.clone(),
+            target: Some(label(target)),
+            url: Some(url),
+            ok,
+            status,
+        });
+    }
+
+    results
+}
+
+fn selected_targets(spec: &PackageSpec, options: &CheckOptions) -> Vec<TargetPlatform> {
+    spec.platform
+        .targets()
+        .into_iter()
+        .filter(|(os, _)| options.os.as_deref().is_none_or(|wanted| wanted == *os))
+        .filter(|(_, arch)| options.arch.as_deref().is_none_or(|wanted| wanted == *arch))
+        .map(|(os, arch)| TargetPlatform { os, arch })
+        .collect()
+}
+
+/// Finds problems that can be spotted without network access: targets
+/// without a file type, file types without targets and placeholders that
+/// are left over after rendering the templates.
+fn lint(spec: &PackageSpec, targets: &[TargetPlatform]) -> Vec<(Option<TargetPlatform>, String)> {
+    let mut problems = Vec::new();
+    if spec.platform.targets().is_empty() {
+        problems.push((None, "no platform mappings".to_string()));
+    }
+
+    for (os, file_type) in [
+        ("macos", &spec.ext.macos),
+        ("linux", &spec.ext.linux),
+        ("windows", &spec.ext.windows),
+    ] {
+        let mapped = spec.platform.targets().iter().any(|(target, _)| *target == os);
+        if file_type.is_some() && !mapped {
+            problems.push((None, format!("file type for {os} but no platform mapping")));
+        }
+    }
+
+    let placeholder = Regex::new(r"\{[^}]*\}").expect("valid placeholder regex");
+    let version = ParsedVersion {
+        tag_name: "v0.0.0".to_string(),
+        non_parsed_string: "v0.0.0".to_string(),
+        semver: None,
+    };
+
+    for target in targets {
+        let rendered = [
+            ("download_template", spec.download_url(&version, target)),
+            (
+                "checksum_template",
+                spec.checksum_url(&version, target).map(Option::unwrap_or_default),
+            ),
+            ("binary_path_template", spec.binary_path(target)),
+        ];
+        for (field, value) in rendered {
+            match value {
+                Ok(value) => {
+                    for unresolved in placeholder.find_iter(&value) {
+                        problems.push((
+                            Some(*target),
+                            format!("unknown placeholder {} in {field}", unresolved.as_str()),
+                        ));
+                    }
+                }
+                Err(err) => problems.push((Some(*target), err.to_string())),
+            }
+        }
+    }
+
+    // A problem shared by several targets is reported once, without a target.
+    let mut unique: Vec<(Option<TargetPlatform>, String)> = Vec::new();
+    for (target, problem) in problems {
+        match unique.iter_mut().find(|(_, existing)| *existing == problem) {
+            Some(entry) => entry.0 = None,
+            None => unique.push((target, problem)),
+        }
+    }
+    unique
+}
+
+fn label(target: &TargetPlatform) -> String { format!("{}-{}", target.os, target.arch) }
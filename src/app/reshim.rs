@@ -0,0 +1,31 @@
+use tracing::info;
+
+use crate::app::installed::installed_specs;
+use crate::domain::package::PackageRegistry;
+use crate::ports::Fs;
+use crate::ports::ProxyInstaller;
+use crate::ports::RootDir;
+
+/// Recreates the proxies of every installed package so they point at the
+/// current `hj` executable.
+pub async fn reshim(
+    registry: &PackageRegistry,
+    root: &impl RootDir,
+    fs: &impl Fs,
+    proxy: &impl ProxyInstaller,
+) -> anyhow::Result<()> {
+    let specs = installed_specs(registry, root, fs).await?;
+    if specs.is_empty() {
+        info!("There are no packages installed");
+        return Ok(());
+    }
+
+    for spec in specs {
+        for binary in spec.binary_names() {
+            proxy.rebuild_proxy(&binary).await?;
+            info!("Rebuilt proxy for {binary}");
+        }
+    }
+
+    Ok(())
+}
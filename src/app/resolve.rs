@@ -1,16 +1,162 @@
+use anyhow::anyhow;
+use semver::VersionReq;
+
+use crate::app::installed::find_installed;
+use crate::domain::package::Channel;
 use crate::domain::package::PackageSpec;
 use crate::domain::version::parse_normal_version;
+use crate::domain::version::parse_version_req;
+use crate::domain::version::semver;
 use crate::domain::version::ParsedVersion;
+use crate::domain::version::RemoteVersion;
+use crate::ports::DownloadCache;
+use crate::ports::Platform;
 use crate::ports::ReleaseProvider;
 
+/// Resolves `requested` to the tag of a release. An exact version that is
+/// among `installed` resolves to the installed tag without looking it up.
 pub async fn resolve_requested_version<R: ReleaseProvider>(
     requested: &str,
     spec: &PackageSpec,
+    installed: &[String],
     release_provider: &R,
 ) -> anyhow::Result<ParsedVersion> {
+    if let Some(channel) = spec.channels.get(requested) {
+        return resolve_channel(requested, channel, spec, release_provider).await;
+    }
+
     if requested == "latest" {
-        release_provider.latest(spec).await
-    } else {
-        parse_normal_version(requested).await
+        return release_provider.latest(spec).await;
+    }
+
+    match parse_version_req(requested) {
+        Some(req) => resolve_range(requested, &req, spec, release_provider).await,
+        None => resolve_exact(requested, spec, installed, release_provider).await,
+    }
+}
+
+/// Finds the tag of the release `requested` names, so `1.2.3` also finds
+/// `v1.2.3` and the other way around. An installed spelling wins, otherwise
+/// the other spelling is only looked up when `requested` is not a release.
+/// Falls back to `requested` itself when neither can be found.
+async fn resolve_exact<R: ReleaseProvider>(
+    requested: &str,
+    spec: &PackageSpec,
+    installed: &[String],
+    release_provider: &R,
+) -> anyhow::Result<ParsedVersion> {
+    if let Some(name) = find_installed(spec, installed, requested) {
+        return parse_normal_version(&name).await;
+    }
+    if !semver(requested)? || release_provider.release(spec, requested).await.is_ok() {
+        return parse_normal_version(requested).await;
+    }
+
+    let other = match requested.strip_prefix('v') {
+        Some(unprefixed) => unprefixed.to_string(),
+        None => format!("v{requested}"),
+    };
+    match release_provider.release(spec, &other).await {
+        Ok(_) => parse_normal_version(&other).await,
+        Err(_) => parse_normal_version(requested).await,
     }
 }
+
+/// Resolves the channel named `name` to the tag it currently stands for.
+async fn resolve_channel<R: ReleaseProvider>(
+    name: &str,
+    channel: &Channel,
+    spec: &PackageSpec,
+    release_provider: &R,
+) -> anyhow::Result<ParsedVersion> {
+    match channel {
+        Channel::Tag(tag) => parse_normal_version(tag).await,
+        Channel::Newest {
+            versions,
+            prerelease,
+        } => {
+            let releases = release_provider.list(spec).await?;
+            spec.newest_matching(releases, versions.as_ref(), *prerelease)
+                .ok_or_else(|| anyhow!("No release of {} is on the '{name}' channel", spec.id))
+        }
+    }
+}
+
+/// Picks the newest stable release whose tag satisfies `req`.
+async fn resolve_range<R: ReleaseProvider>(
+    requested: &str,
+    req: &VersionReq,
+    spec: &PackageSpec,
+    release_provider: &R,
+) -> anyhow::Result<ParsedVersion> {
+    let releases = release_provider.list(spec).await?;
+    spec.newest_release(releases, Some(req))
+        .ok_or_else(|| anyhow!("No release of {} matches '{requested}'", spec.id))
+}
+
+/// Resolves `requested` the way installs do: against the releases, or offline
+/// against the installed versions and the archives in the download cache.
+pub async fn resolve_for_install<R: ReleaseProvider>(
+    requested: &str,
+    spec: &PackageSpec,
+    installed: &[String],
+    platform: &impl Platform,
+    cache: &impl DownloadCache,
+    release_provider: &R,
+    offline: bool,
+) -> anyhow::Result<ParsedVersion> {
+    if !offline {
+        return resolve_requested_version(requested, spec, installed, release_provider).await;
+    }
+
+    let mut available = installed.to_vec();
+    for entry in cache.entries().await? {
+        if let Some(tag) = spec.tag_for_url(&entry.url, platform) {
+            available.push(tag);
+        }
+    }
+    resolve_offline(requested, spec, &available).await
+}
+
+/// Resolves `requested` without network access, against the `available`
+/// tags. An exact version that is not among them resolves to itself.
+async fn resolve_offline(
+    requested: &str,
+    spec: &PackageSpec,
+    available: &[String],
+) -> anyhow::Result<ParsedVersion> {
+    let releases = available
+        .iter()
+        .map(|tag| RemoteVersion {
+            name: tag.clone(),
+            tag_name: tag.clone(),
+            prerelease: spec.version(tag).semver().is_some_and(|semver| !semver.pre.is_empty()),
+            published_at: None,
+            assets: Vec::new(),
+        })
+        .collect();
+
+    let newest = match spec.channels.get(requested) {
+        Some(Channel::Tag(tag)) => return parse_normal_version(tag).await,
+        Some(Channel::Newest {
+            versions,
+            prerelease,
+        }) => spec.newest_matching(releases, versions.as_ref(), *prerelease),
+        None if requested == "latest" => spec.newest_release(releases, None),
+        None => match parse_version_req(requested) {
+            Some(req) => spec.newest_release(releases, Some(&req)),
+            None => {
+                let tag = find_installed(spec, available, requested);
+                return parse_normal_version(tag.as_deref().unwrap_or(requested)).await;
+            }
+        },
+    };
+
+    newest.ok_or_else(|| {
+        anyhow!(
+            "No installed or cached version of {} matches '{requested}'. Run without --offline to \
+             look it up.",
+            spec.id
+        )
+    })
+}
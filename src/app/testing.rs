@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::domain::package::PackageRegistry;
+use crate::domain::package::PackageSpec;
+use crate::domain::package::RegistryLayer;
+
+/// The `tool` package on linux, with `extra` TOML appended to its entry, for
+/// example a `[package.channels]` table.
+pub fn tool_spec(extra: &str) -> Arc<PackageSpec> {
+    let contents = format!(
+        r#"
+        [defaults.platform.linux]
+        x86_64 = "x86_64-unknown-linux-gnu"
+
+        [defaults.ext]
+        linux = "tar.gz"
+
+        [[package]]
+        id = "tool"
+        alias = "tool"
+        repo = "acme/tool"
+        download_template = "{{base}}/{{repo}}/releases/download/{{version}}/tool.{{file_type}}"
+        {extra}
+        "#
+    );
+    let layer = RegistryLayer {
+        source: "test".to_string(),
+        contents,
+        project: false,
+    };
+    match PackageRegistry::load_layers(vec![layer]) {
+        Ok(registry) => registry.resolve("tool").unwrap(),
+        Err(err) => panic!("{err:#}"),
+    }
+}
@@ -1,44 +1,79 @@
+use anyhow::anyhow;
 use anyhow::Error;
 use anyhow::Result;
 use tracing::info;
 
-use crate::app::resolve::resolve_requested_version;
+use crate::app::installed::installed_match;
+use crate::app::installed::installed_versions;
 use crate::domain::package::Package;
 use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_normal_version;
 use crate::ports::Fs;
 use crate::ports::Paths;
 use crate::ports::Platform;
-use crate::ports::ReleaseProvider;
+use crate::ports::ProxyInstaller;
 use crate::ports::UsedVersionStore;
 
+/// Uninstalls the installed version `requested_version` refers to: the same
+/// version under any spelling, the tag of a channel, or the newest installed
+/// one within a range.
+#[allow(clippy::too_many_arguments)]
 pub async fn uninstall_requested(
     spec: std::sync::Arc<PackageSpec>,
     requested_version: String,
-    release_provider: &impl ReleaseProvider,
+    keep_used: bool,
     platform: &impl Platform,
     fs: &impl Fs,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
+    proxy: &impl ProxyInstaller,
 ) -> Result<(), Error> {
-    let parsed = resolve_requested_version(&requested_version, &spec, release_provider).await?;
-    let package = Package::with_parsed(spec, parsed, platform)?;
-    uninstall(package, fs, paths, used_store).await
+    let downloads_dir = paths.downloads_dir(Package::from_spec(spec.clone(), platform)?).await?;
+    let installed = installed_versions(fs, &downloads_dir).await.unwrap_or_default();
+    let version = installed_match(&spec, &requested_version, &installed).ok_or_else(|| {
+        anyhow!(
+            "No installed version of {} matches '{requested_version}'",
+            spec.id
+        )
+    })?;
+    let package = Package::with_parsed(spec, parse_normal_version(&version).await?, platform)?;
+    uninstall(package, keep_used, fs, paths, used_store, proxy).await
 }
 
+/// Removes an installed version. When it was the used one, the highest
+/// remaining version takes its place, or the proxies are removed if none is
+/// left. With `keep_used` the used version is refused instead.
 pub async fn uninstall(
     package: Package,
+    keep_used: bool,
     fs: &impl Fs,
     paths: &impl Paths,
     used_store: &impl UsedVersionStore,
+    proxy: &impl ProxyInstaller,
 ) -> Result<(), Error> {
     let parsed_version = package.version().expect("Failed to parse version");
-    let version = parsed_version.non_parsed_string.clone();
-    let used_version = used_store.current(package.clone()).await?.unwrap_or_default();
-    let same_version = used_version == version;
+    let spec = package.spec();
+    let requested = spec.version(&parsed_version.non_parsed_string);
+    let remaining_dir = paths.downloads_dir(package.clone()).await?;
+    // Matches the installed directory, so `1.2.3` removes an installed
+    // `v1.2.3`.
+    let version = installed_versions(fs, &remaining_dir)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|installed| spec.version(installed) == requested)
+        .unwrap_or_else(|| parsed_version.non_parsed_string.clone());
+    let used_version = used_store.current(package.clone()).await?;
+    let same_version = used_version.is_some_and(|used| spec.version(&used) == requested);
 
-    let mut downloads = paths.downloads_dir(package.clone()).await?;
-    let location = downloads.join("used");
-    downloads.push(&version);
+    if same_version && keep_used {
+        return Err(anyhow!(
+            "{version} is in use and --keep-used was given, switch to another version first"
+        ));
+    }
+
+    let location = remaining_dir.join("used");
+    let downloads = remaining_dir.join(&version);
 
     if fs.remove_dir_all(&downloads).await.is_ok() {
         info!("Successfully uninstalled {} installation", &version);
@@ -50,11 +85,74 @@ pub async fn uninstall(
         return Ok(());
     }
 
+    if let Some(next) = highest_version(
+        package.spec(),
+        installed_versions(fs, &remaining_dir).await?,
+    ) {
+        used_store.set_current(package, &next).await?;
+        info!("{version} was in use, switched to {next}");
+        return Ok(());
+    }
+
     if fs.remove_file(&location).await.is_ok() {
         info!("Successfully removed {} from used versions", &version);
-    } else {
-        info!("There's nothing to uninstall");
     }
 
+    for name in package.binary_names() {
+        proxy.remove_proxy(&name).await?;
+    }
+    info!(
+        "No versions of {} left, removed its proxies",
+        package.alias()
+    );
+
     Ok(())
 }
+
+fn highest_version(spec: &PackageSpec, versions: Vec<String>) -> Option<String> {
+    versions.into_iter().max_by_key(|version| spec.version(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testing::tool_spec;
+
+    fn highest(versions: &[&str]) -> Option<String> {
+        highest_version(
+            &tool_spec(""),
+            versions.iter().map(|version| version.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn the_highest_remaining_version_is_used_next() {
+        assert_eq!(
+            highest(&["v1.9.0", "v1.10.0", "v1.2.0"]).as_deref(),
+            Some("v1.10.0")
+        );
+        assert_eq!(
+            highest(&["1.2.0", "v1.3.0", "1.1.0"]).as_deref(),
+            Some("v1.3.0")
+        );
+    }
+
+    #[test]
+    fn prereleases_rank_below_their_release_and_channels_below_numbers() {
+        assert_eq!(
+            highest(&["v2.0.0-rc.1", "v1.9.0"]).as_deref(),
+            Some("v2.0.0-rc.1")
+        );
+        assert_eq!(
+            highest(&["v2.0.0-rc.1", "v2.0.0"]).as_deref(),
+            Some("v2.0.0")
+        );
+        assert_eq!(highest(&["nightly", "v0.1.0"]).as_deref(), Some("v0.1.0"));
+        assert_eq!(highest(&["nightly"]).as_deref(), Some("nightly"));
+    }
+
+    #[test]
+    fn nothing_is_used_next_when_nothing_remains() {
+        assert_eq!(highest(&[]), None);
+    }
+}
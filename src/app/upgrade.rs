@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use futures_util::future::join_all;
+use tracing::error;
+use tracing::info;
+
+use crate::app::download;
+use crate::app::install::install;
+use crate::app::install::install_version;
+use crate::app::install::installed_stamp;
+use crate::app::install::moving_release_stamp;
+use crate::app::uninstall::uninstall;
+use crate::domain::package::Package;
+use crate::domain::package::PackageSpec;
+use crate::domain::version::parse_normal_version;
+use crate::ports::Archive;
+use crate::ports::DownloadCache;
+use crate::ports::Downloader;
+use crate::ports::Fs;
+use crate::ports::Lock;
+use crate::ports::Paths;
+use crate::ports::Platform;
+use crate::ports::ProxyInstaller;
+use crate::ports::ReleaseProvider;
+use crate::ports::UsedVersionStore;
+
+/// Upgrades every package in `specs` to its latest release concurrently and
+/// marks it as used. A failing package is reported without stopping the
+/// others; the call fails at the end if any of them did.
+#[allow(clippy::too_many_arguments)]
+pub async fn upgrade<R, D, A, F>(
+    specs: Vec<Arc<PackageSpec>>,
+    prune: bool,
+    release_provider: &R,
+    downloader: &D,
+    archive: &A,
+    fs: &F,
+    platform: &impl Platform,
+    lock: &impl Lock,
+    used_store: &impl UsedVersionStore,
+    paths: &impl Paths,
+    proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+) -> anyhow::Result<()>
+where
+    R: ReleaseProvider,
+    D: Downloader,
+    A: Archive,
+    F: Fs,
+{
+    let upgrades = specs.iter().map(|spec| async move {
+        let result = upgrade_package(
+            spec.clone(),
+            prune,
+            release_provider,
+            downloader,
+            archive,
+            fs,
+            platform,
+            lock,
+            used_store,
+            paths,
+            proxy,
+            cache,
+        )
+        .await;
+        (spec.id.clone(), result)
+    });
+
+    let mut failed = Vec::new();
+    for (id, result) in join_all(upgrades).await {
+        if let Err(err) = result {
+            error!("Failed to upgrade {id}: {err}");
+            failed.push(id);
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} of {} packages failed to upgrade: {}",
+            failed.len(),
+            specs.len(),
+            failed.join(", ")
+        ))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upgrade_package<R, D, A, F>(
+    spec: Arc<PackageSpec>,
+    prune: bool,
+    release_provider: &R,
+    downloader: &D,
+    archive: &A,
+    fs: &F,
+    platform: &impl Platform,
+    lock: &impl Lock,
+    used_store: &impl UsedVersionStore,
+    paths: &impl Paths,
+    proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+) -> anyhow::Result<()>
+where
+    R: ReleaseProvider,
+    D: Downloader,
+    A: Archive,
+    F: Fs,
+{
+    let current = used_store.current(Package::from_spec(spec.clone(), platform)?).await?;
+    if let Some(current) = current.as_deref().filter(|tag| spec.is_moving_tag(tag)) {
+        return rebuild_moving_tag(
+            spec.clone(),
+            current,
+            release_provider,
+            downloader,
+            archive,
+            fs,
+            platform,
+            lock,
+            used_store,
+            paths,
+            proxy,
+            cache,
+        )
+        .await;
+    }
+
+    let latest = release_provider.latest(&spec).await?;
+    let package = Package::with_parsed(spec.clone(), latest.clone(), platform)?;
+
+    if let Some(current) = current.as_deref() {
+        if spec.version(current).is_at_least(&spec.version(&latest.tag_name)) {
+            info!("{} is up to date ({current})", spec.id);
+            return Ok(());
+        }
+    }
+
+    let installed = install_version(
+        spec.clone(),
+        latest.tag_name.clone(),
+        release_provider,
+        downloader,
+        archive,
+        fs,
+        platform,
+        lock,
+        paths,
+        proxy,
+        cache,
+        false,
+    )
+    .await?;
+    let latest = installed.version().unwrap_or(latest);
+    used_store.set_current(package, &latest.tag_name).await?;
+
+    match current {
+        Some(previous) => {
+            info!(
+                "Upgraded {} from {previous} to {}",
+                spec.id, latest.tag_name
+            );
+            if prune {
+                let previous = parse_normal_version(&previous).await?;
+                let package = Package::with_parsed(spec, previous, platform)?;
+                uninstall(package, false, fs, paths, used_store, proxy).await?;
+            }
+        }
+        None => info!("Upgraded {} to {}", spec.id, latest.tag_name),
+    }
+
+    Ok(())
+}
+
+/// Reinstalls the moving tag `tag`, such as `nightly`, when upstream rebuilt
+/// it since it was installed.
+#[allow(clippy::too_many_arguments)]
+async fn rebuild_moving_tag<R, D, A, F>(
+    spec: Arc<PackageSpec>,
+    tag: &str,
+    release_provider: &R,
+    downloader: &D,
+    archive: &A,
+    fs: &F,
+    platform: &impl Platform,
+    lock: &impl Lock,
+    used_store: &impl UsedVersionStore,
+    paths: &impl Paths,
+    proxy: &impl ProxyInstaller,
+    cache: &impl DownloadCache,
+) -> anyhow::Result<()>
+where
+    R: ReleaseProvider,
+    D: Downloader,
+    A: Archive,
+    F: Fs,
+{
+    let package = Package::with_parsed(spec.clone(), parse_normal_version(tag).await?, platform)?;
+    let download_url = download::download_url(&package, platform);
+    let version_dir = paths.downloads_dir(package).await?.join(tag);
+    let stamp = moving_release_stamp(&spec, tag, &download_url, release_provider).await?;
+
+    if installed_stamp(fs, &version_dir).await? == stamp {
+        info!("{} is up to date ({tag})", spec.id);
+        return Ok(());
+    }
+
+    install(
+        spec.clone(),
+        tag.to_string(),
+        release_provider,
+        downloader,
+        archive,
+        fs,
+        platform,
+        lock,
+        used_store,
+        paths,
+        proxy,
+        cache,
+        false,
+    )
+    .await?;
+
+    let built = stamp
+        .and_then(|stamp| stamp.updated_at)
+        .map(|at| format!(" built {}", at.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
+    info!("Upgraded {} to the {tag}{built}", spec.id);
+    Ok(())
+}
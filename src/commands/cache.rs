@@ -0,0 +1,36 @@
+use clap::Parser;
+use clap::Subcommand;
+
+use crate::adapters::cache::FsDownloadCache;
+use crate::adapters::output::StdoutOutput;
+use crate::app::cache::cache_size;
+use crate::app::cache::clean_cache;
+use crate::app::cache::list_cache;
+
+#[derive(Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Show the cached archives and the URLs they were downloaded from
+    List,
+    /// Remove every cached archive
+    Clean,
+    /// Show the total size of the cache
+    Size,
+}
+
+pub async fn run(args: Args, ctx: &crate::Context) -> miette::Result<()> {
+    let cache = FsDownloadCache::new(ctx.dirs.cache_dir.clone());
+    let output = StdoutOutput;
+
+    match args.command {
+        Command::List => list_cache(ctx.output_format.clone(), &cache, &output).await,
+        Command::Clean => clean_cache(&cache).await,
+        Command::Size => cache_size(ctx.output_format.clone(), &cache, &output).await,
+    }
+    .map_err(|e| miette::miette!(e))
+}
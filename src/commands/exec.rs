@@ -0,0 +1,91 @@
+use clap::Parser;
+
+use crate::adapters::archive::LocalArchive;
+use crate::adapters::cache::FsDownloadCache;
+use crate::adapters::downloader::ReqwestDownloader;
+use crate::adapters::fs::TokioFs;
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::adapters::pin_store::ProjectPinStore;
+use crate::app::exec::exec;
+use crate::ports::Env;
+
+#[derive(Parser)]
+pub struct Args {
+    /// `<package>[@<version>]`; naming one of the package's binaries runs it
+    pub target: String,
+
+    #[arg(long, help = "install the version first if it is missing")]
+    pub install: bool,
+
+    /// Arguments passed to the program, after `--`
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+/// Runs a version of a package without marking it as used, passing its exit
+/// code through.
+pub async fn run(
+    args: Args,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    let (name, version) = match args.target.split_once('@') {
+        Some((_, "")) => {
+            return Err(miette::miette!(
+                "Missing version after '@' in '{}'",
+                args.target
+            ))
+        }
+        Some((name, version)) => (name, Some(version.to_string())),
+        None => (args.target.as_str(), None),
+    };
+    let spec = ctx.packages.resolve(name).map_err(|e| miette::miette!(e))?;
+    // A package named by one of its binaries runs that one, otherwise its
+    // first.
+    let binaries = spec.binary_names();
+    let binary = match binaries.iter().any(|binary| binary == name) {
+        true => name,
+        false => &binaries[0],
+    };
+
+    let provider = GitHubReleaseProvider::new(client);
+    let downloader = ReqwestDownloader::new(client);
+    let archive = LocalArchive;
+    let fs = TokioFs;
+    let platform = crate::adapters::platform::StdPlatform;
+    let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
+    let lock = crate::adapters::lock::FileLock::new(paths.clone());
+    let proxy = crate::adapters::proxy::ProxyFsLinker::new(
+        paths.clone(),
+        crate::adapters::env::StdEnv,
+        crate::adapters::fs::TokioFs,
+    );
+    let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+    let cwd = crate::adapters::env::StdEnv.current_dir().map_err(|e| miette::miette!(e))?;
+    let pins = ProjectPinStore::new(cwd);
+    let cache = FsDownloadCache::new(ctx.dirs.cache_dir.clone());
+    let process = crate::adapters::process::TokioProcess;
+
+    exec(
+        spec,
+        binary,
+        version,
+        &args.args,
+        args.install,
+        &provider,
+        &downloader,
+        &archive,
+        &fs,
+        &platform,
+        &lock,
+        &used_store,
+        &pins,
+        &paths,
+        &proxy,
+        &cache,
+        &process,
+        ctx.offline,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
@@ -0,0 +1,45 @@
+use clap::Parser;
+
+use crate::adapters::fs::TokioFs;
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::adapters::output::StdoutOutput;
+use crate::app::info::info;
+
+#[derive(Parser)]
+pub struct Args {
+    pub package: String,
+
+    /// Version to render the URLs for
+    #[arg(default_value = "latest")]
+    pub version: String,
+}
+
+/// Prints what a package spec resolves to on this platform, useful when
+/// writing a custom `packages.toml`.
+pub async fn run(
+    args: Args,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    let spec = ctx.packages.resolve(&args.package).map_err(|e| miette::miette!(e))?;
+    let provider = GitHubReleaseProvider::new(client);
+    let platform = crate::adapters::platform::StdPlatform;
+    let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
+    let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+    let fs = TokioFs;
+    let output = StdoutOutput;
+
+    info(
+        spec,
+        args.version,
+        ctx.output_format.clone(),
+        &provider,
+        &platform,
+        &paths,
+        &used_store,
+        &fs,
+        &output,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
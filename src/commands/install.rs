@@ -1,6 +1,7 @@
 use clap::Parser;
 
 use crate::adapters::archive::LocalArchive;
+use crate::adapters::cache::FsDownloadCache;
 use crate::adapters::downloader::ReqwestDownloader;
 use crate::adapters::fs::TokioFs;
 use crate::adapters::github_release::GitHubReleaseProvider;
@@ -24,16 +25,14 @@ pub async fn run(
     let fs = TokioFs;
     let platform = crate::adapters::platform::StdPlatform;
     let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
-    let lock = crate::adapters::lock::FileLock::from_paths(&paths)
-        .await
-        .map_err(|e| miette::miette!(e))?;
-    let proxy = crate::adapters::proxy::ProxyFsCopier::new(
+    let lock = crate::adapters::lock::FileLock::new(paths.clone());
+    let proxy = crate::adapters::proxy::ProxyFsLinker::new(
         paths.clone(),
         crate::adapters::env::StdEnv,
         crate::adapters::fs::TokioFs,
-        crate::adapters::process::TokioProcess,
     );
     let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+    let cache = FsDownloadCache::new(ctx.dirs.cache_dir.clone());
 
     app_install::install(
         spec,
@@ -47,6 +46,8 @@ pub async fn run(
         &used_store,
         &paths,
         &proxy,
+        &cache,
+        ctx.offline,
     )
     .await
     .map_err(|e| miette::miette!(e))
@@ -1,28 +1,59 @@
 use crate::adapters::fs::TokioFs;
+use crate::adapters::github_release::GitHubReleaseProvider;
 use crate::adapters::output::StdoutOutput;
+use crate::app::installed::installed_specs;
+use crate::app::list::list_all;
 use crate::app::list::list_installed;
 use crate::domain::package::Package;
 
 #[derive(clap::Parser)]
 pub struct Args {
-    pub package: String,
+    /// Package to list; without it every installed package is shown
+    pub package: Option<String>,
+
+    /// Show when each version was published, fetching the package's release
+    /// list
+    #[arg(long)]
+    pub published: bool,
 }
 
 pub async fn run(
     args: Args,
     ctx: &crate::Context,
-    _client: Option<&reqwest::Client>,
+    client: Option<&reqwest::Client>,
 ) -> miette::Result<()> {
-    let spec = ctx.packages.resolve(&args.package).map_err(|e| miette::miette!(e))?;
     let output = StdoutOutput;
     let fs = TokioFs;
     let platform = crate::adapters::platform::StdPlatform;
     let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
     let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+
+    let Some(package) = args.package else {
+        let specs = installed_specs(&ctx.packages, &ctx.dirs, &fs)
+            .await
+            .map_err(|e| miette::miette!(e))?;
+        return list_all(
+            specs,
+            ctx.output_format.clone(),
+            &output,
+            &platform,
+            &paths,
+            &used_store,
+            &fs,
+        )
+        .await
+        .map_err(|e| miette::miette!(e));
+    };
+
+    let spec = ctx.packages.resolve(&package).map_err(|e| miette::miette!(e))?;
     let package = Package::from_spec(spec, &platform).map_err(|e| miette::miette!(e))?;
+    let provider = GitHubReleaseProvider::new(client);
     list_installed(
         package,
         ctx.output_format.clone(),
+        args.published,
+        ctx.offline,
+        &provider,
         &output,
         &paths,
         &used_store,
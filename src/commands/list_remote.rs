@@ -1,12 +1,31 @@
+use chrono::NaiveDate;
+use regex::Regex;
+
 use crate::adapters::fs::TokioFs;
 use crate::adapters::github_release::GitHubReleaseProvider;
 use crate::adapters::output::StdoutOutput;
 use crate::app::list_remote::list_remote as app_list_remote;
+use crate::app::list_remote::RemoteFilter;
 use crate::domain::package::Package;
 
 #[derive(clap::Parser)]
 pub struct Args {
     pub package: String,
+
+    #[arg(long, help = "show at most this many versions")]
+    pub limit: Option<usize>,
+
+    #[arg(long, help = "include prereleases")]
+    pub pre: bool,
+
+    #[arg(
+        long,
+        help = "only versions published on or after this date (YYYY-MM-DD)"
+    )]
+    pub since: Option<NaiveDate>,
+
+    #[arg(long, help = "only tags matching this regex")]
+    pub tag: Option<String>,
 }
 
 pub async fn run(
@@ -22,10 +41,22 @@ pub async fn run(
     let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
     let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
     let fs = TokioFs;
+    let filter = RemoteFilter {
+        limit: args.limit,
+        include_prereleases: args.pre,
+        since: args.since,
+        tag: args
+            .tag
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| miette::miette!(e))?,
+    };
 
     app_list_remote(
         package,
         ctx.output_format.clone(),
+        &filter,
         &provider,
         &output,
         &paths,
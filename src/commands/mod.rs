@@ -1,7 +1,16 @@
+pub mod cache;
 pub mod erase;
+pub mod exec;
+pub mod info;
 pub mod install;
 pub mod list;
 pub mod list_remote;
+pub mod outdated;
+pub mod pin;
 pub mod prefix;
+pub mod prune;
+pub mod registry;
+pub mod reshim;
 pub mod uninstall;
+pub mod upgrade;
 pub mod use_cmd;
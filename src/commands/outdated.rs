@@ -0,0 +1,32 @@
+use crate::adapters::fs::TokioFs;
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::adapters::output::StdoutOutput;
+use crate::app::installed::installed_specs;
+use crate::app::outdated::outdated;
+
+/// Reports installed packages whose used version is behind the latest
+/// release, exiting with an error if there are any.
+pub async fn run(ctx: &crate::Context, client: Option<&reqwest::Client>) -> miette::Result<()> {
+    let fs = TokioFs;
+    let specs = installed_specs(&ctx.packages, &ctx.dirs, &fs)
+        .await
+        .map_err(|e| miette::miette!(e))?;
+    let provider = GitHubReleaseProvider::new(client);
+    let output = StdoutOutput;
+    let platform = crate::adapters::platform::StdPlatform;
+    let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
+    let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+
+    outdated(
+        specs,
+        ctx.output_format.clone(),
+        &provider,
+        &output,
+        &platform,
+        &used_store,
+        &paths,
+        &fs,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
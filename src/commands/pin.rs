@@ -0,0 +1,32 @@
+use clap::Parser;
+
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::adapters::pin_store::ProjectPinStore;
+use crate::app::pin::pin;
+use crate::ports::Env;
+
+#[derive(Parser)]
+pub struct Args {
+    pub package: String,
+    pub version: String,
+}
+
+/// Writes a `.hj-versions` pin in the current directory. The proxy prefers
+/// the nearest pin over the globally used version.
+pub async fn run(
+    args: Args,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    let spec = ctx.packages.resolve(&args.package).map_err(|e| miette::miette!(e))?;
+    let provider = GitHubReleaseProvider::new(client);
+    let platform = crate::adapters::platform::StdPlatform;
+    let cwd = crate::adapters::env::StdEnv.current_dir().map_err(|e| miette::miette!(e))?;
+    let pins = ProjectPinStore::new(cwd);
+    let fs = crate::adapters::fs::TokioFs;
+    let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
+
+    pin(spec, args.version, &provider, &platform, &fs, &paths, &pins)
+        .await
+        .map_err(|e| miette::miette!(e))
+}
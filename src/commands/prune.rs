@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ArgGroup;
+use clap::Parser;
+
+use crate::adapters::fs::TokioFs;
+use crate::adapters::path::FsPaths;
+use crate::adapters::pin_store::ProjectPinStore;
+use crate::adapters::used_store::UsedFileStore;
+use crate::app::installed::installed_specs;
+use crate::app::prune::prune;
+use crate::app::prune::RetentionPolicy;
+use crate::domain::duration::parse_duration;
+use crate::ports::Env;
+
+#[derive(Parser)]
+#[command(group(ArgGroup::new("policy").required(true).multiple(true).args(["keep", "older_than"])))]
+pub struct Args {
+    /// Prune only this package instead of every installed one
+    pub package: Option<String>,
+
+    #[arg(long, help = "keep the newest N versions")]
+    pub keep: Option<usize>,
+
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "remove versions installed longer ago than this, like 12h, 30d or 8w"
+    )]
+    pub older_than: Option<Duration>,
+
+    #[arg(
+        long = "project",
+        value_name = "DIR",
+        help = "keep the versions pinned for this project, repeatable; defaults to the current \
+                directory"
+    )]
+    pub projects: Vec<PathBuf>,
+
+    #[arg(long, help = "show what would be removed without removing it")]
+    pub dry_run: bool,
+}
+
+pub async fn run(args: Args, ctx: &crate::Context) -> miette::Result<()> {
+    let fs = TokioFs;
+    let specs = match args.package {
+        Some(package) => vec![ctx.packages.resolve(&package).map_err(|e| miette::miette!(e))?],
+        None => installed_specs(&ctx.packages, &ctx.dirs, &fs)
+            .await
+            .map_err(|e| miette::miette!(e))?,
+    };
+
+    let policy = RetentionPolicy {
+        keep: args.keep,
+        older_than: args.older_than,
+    };
+    let platform = crate::adapters::platform::StdPlatform;
+    let paths = FsPaths::new(ctx.dirs.root_dir.clone());
+    let used_store = UsedFileStore::new(paths.clone());
+    let projects = match args.projects.is_empty() {
+        true => vec![crate::adapters::env::StdEnv.current_dir().map_err(|e| miette::miette!(e))?],
+        false => args.projects,
+    };
+    let pins: Vec<_> = projects.into_iter().map(ProjectPinStore::new).collect();
+
+    prune(
+        specs,
+        &policy,
+        args.dry_run,
+        &platform,
+        &fs,
+        &paths,
+        &used_store,
+        &pins,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use clap::Subcommand;
+
+use crate::adapters::downloader::ReqwestDownloader;
+use crate::adapters::env::StdEnv;
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::adapters::output::StdoutOutput;
+use crate::adapters::registry_source::CachedRegistrySources;
+use crate::app::registry::check_registry;
+use crate::app::registry::update_sources;
+use crate::app::registry::CheckOptions;
+use crate::domain::package::PackageRegistry;
+
+#[derive(Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check that every package has a release asset on each of its platforms
+    Check(CheckArgs),
+    /// Fetch every registry source again, ignoring its ttl
+    Update,
+}
+
+#[derive(Parser)]
+pub struct CheckArgs {
+    /// Packages to check, all of them by default
+    pub packages: Vec<String>,
+
+    #[arg(long, help = "only lint the templates, without network access")]
+    pub lint: bool,
+
+    #[arg(long, help = "check a packages.toml other than the active one")]
+    pub file: Option<PathBuf>,
+
+    #[arg(long, value_parser = ["macos", "linux", "windows"], help = "only check this OS")]
+    pub os: Option<String>,
+
+    #[arg(long, value_parser = ["aarch64", "x86_64"], help = "only check this arch")]
+    pub arch: Option<String>,
+}
+
+pub async fn run(
+    args: Args,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    match args.command {
+        Command::Check(args) => check(args, ctx, client).await,
+        Command::Update => update(ctx, client).await,
+    }
+}
+
+async fn update(ctx: &crate::Context, client: Option<&reqwest::Client>) -> miette::Result<()> {
+    let layers = crate::registry_layers(&StdEnv, &ctx.dirs)?;
+    let sources = CachedRegistrySources::new(ctx.dirs.registry_dir(), client);
+    update_sources(layers, &sources).await.map_err(|e| miette::miette!(e))
+}
+
+async fn check(
+    args: CheckArgs,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    let loaded;
+    let registry = match &args.file {
+        Some(file) => {
+            loaded = PackageRegistry::load_from_path(file).map_err(|e| miette::miette!(e))?;
+            &loaded
+        }
+        None => &ctx.packages,
+    };
+
+    let specs = match args.packages.is_empty() {
+        true => registry.specs(),
+        false => args
+            .packages
+            .iter()
+            .map(|package| registry.resolve(package))
+            .collect::<anyhow::Result<_>>()
+            .map_err(|e| miette::miette!(e))?,
+    };
+
+    let options = CheckOptions {
+        lint: args.lint,
+        os: args.os,
+        arch: args.arch,
+    };
+    let provider = GitHubReleaseProvider::new(client);
+    let downloader = ReqwestDownloader::new(client);
+    let output = StdoutOutput;
+
+    check_registry(
+        specs,
+        &options,
+        ctx.output_format.clone(),
+        &provider,
+        &downloader,
+        &output,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
@@ -0,0 +1,16 @@
+use crate::adapters::env::StdEnv;
+use crate::adapters::fs::TokioFs;
+use crate::adapters::path::FsPaths;
+use crate::adapters::proxy::ProxyFsLinker;
+use crate::app::reshim::reshim;
+
+/// Rebuilds every proxy in the installation dir, e.g. after upgrading `hj`
+/// itself or moving its executable.
+pub async fn run(ctx: &crate::Context) -> miette::Result<()> {
+    let paths = FsPaths::new(ctx.dirs.root_dir.clone());
+    let proxy = ProxyFsLinker::new(paths, StdEnv, TokioFs);
+
+    reshim(&ctx.packages, &ctx.dirs, &TokioFs, &proxy)
+        .await
+        .map_err(|e| miette::miette!(e))
+}
@@ -1,7 +1,6 @@
 use clap::Parser;
 
 use crate::adapters::fs::TokioFs;
-use crate::adapters::github_release::GitHubReleaseProvider;
 use crate::adapters::path::FsPaths;
 use crate::adapters::used_store::UsedFileStore;
 use crate::app::uninstall::uninstall_requested;
@@ -10,28 +9,32 @@ use crate::app::uninstall::uninstall_requested;
 pub struct Args {
     pub package: String,
     pub version: String,
+
+    #[arg(long, help = "refuse to remove the version currently in use")]
+    pub keep_used: bool,
 }
 
-pub async fn run(
-    args: Args,
-    ctx: &crate::Context,
-    client: Option<&reqwest::Client>,
-) -> miette::Result<()> {
+pub async fn run(args: Args, ctx: &crate::Context) -> miette::Result<()> {
     let spec = ctx.packages.resolve(&args.package).map_err(|e| miette::miette!(e))?;
     let paths = FsPaths::new(ctx.dirs.root_dir.clone());
     let used_store = UsedFileStore::new(paths.clone());
     let fs = TokioFs;
     let platform = crate::adapters::platform::StdPlatform;
-    let provider = GitHubReleaseProvider::new(client);
+    let proxy = crate::adapters::proxy::ProxyFsLinker::new(
+        paths.clone(),
+        crate::adapters::env::StdEnv,
+        crate::adapters::fs::TokioFs,
+    );
 
     uninstall_requested(
         spec,
         args.version,
-        &provider,
+        args.keep_used,
         &platform,
         &fs,
         &paths,
         &used_store,
+        &proxy,
     )
     .await
     .map_err(|e| miette::miette!(e))
@@ -0,0 +1,68 @@
+use clap::Parser;
+
+use crate::adapters::archive::LocalArchive;
+use crate::adapters::cache::FsDownloadCache;
+use crate::adapters::downloader::ReqwestDownloader;
+use crate::adapters::fs::TokioFs;
+use crate::adapters::github_release::GitHubReleaseProvider;
+use crate::app::installed::installed_specs;
+use crate::app::upgrade::upgrade;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub package: Option<String>,
+
+    #[arg(long, help = "upgrade every installed package")]
+    pub all: bool,
+
+    #[arg(long, help = "remove the previously used version after upgrading")]
+    pub prune: bool,
+}
+
+/// Installs the latest release of one or all installed packages and switches
+/// to it.
+pub async fn run(
+    args: Args,
+    ctx: &crate::Context,
+    client: Option<&reqwest::Client>,
+) -> miette::Result<()> {
+    let fs = TokioFs;
+    let specs = match args.package {
+        Some(package) => vec![ctx.packages.resolve(&package).map_err(|e| miette::miette!(e))?],
+        None => installed_specs(&ctx.packages, &ctx.dirs, &fs)
+            .await
+            .map_err(|e| miette::miette!(e))?,
+    };
+
+    let provider = GitHubReleaseProvider::new(client);
+    let downloader = ReqwestDownloader::new(client);
+    let archive = LocalArchive;
+    let platform = crate::adapters::platform::StdPlatform;
+    let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
+    let lock = crate::adapters::lock::FileLock::new(paths.clone());
+    let proxy = crate::adapters::proxy::ProxyFsLinker::new(
+        paths.clone(),
+        crate::adapters::env::StdEnv,
+        crate::adapters::fs::TokioFs,
+    );
+    let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
+    let cache = FsDownloadCache::new(ctx.dirs.cache_dir.clone());
+
+    upgrade(
+        specs,
+        args.prune,
+        &provider,
+        &downloader,
+        &archive,
+        &fs,
+        &platform,
+        &lock,
+        &used_store,
+        &paths,
+        &proxy,
+        &cache,
+    )
+    .await
+    .map_err(|e| miette::miette!(e))
+}
@@ -1,13 +1,16 @@
 use tracing::info;
 
 use crate::adapters::archive::LocalArchive;
+use crate::adapters::cache::FsDownloadCache;
 use crate::adapters::downloader::ReqwestDownloader;
 use crate::adapters::fs::TokioFs;
 use crate::adapters::github_release::GitHubReleaseProvider;
 use crate::app::install;
-use crate::app::resolve::resolve_requested_version;
+use crate::app::installed::installed_versions;
+use crate::app::resolve::resolve_for_install;
 use crate::domain::package::Package;
 use crate::domain::package::PackageSpec;
+use crate::ports::Paths;
 use crate::ports::ProxyInstaller;
 use crate::ports::UsedVersionStore;
 
@@ -25,9 +28,18 @@ pub async fn run(
     let spec = ctx.packages.resolve(&args.package).map_err(|e| miette::miette!(e))?;
     let paths = crate::adapters::path::FsPaths::new(ctx.dirs.root_dir.clone());
     let platform = crate::adapters::platform::StdPlatform;
-    use_cmd(client, spec, args.version, &paths, &platform)
-        .await
-        .map_err(|err| miette::miette!(err))?;
+    let cache = FsDownloadCache::new(ctx.dirs.cache_dir.clone());
+    use_cmd(
+        client,
+        spec,
+        args.version,
+        &paths,
+        &platform,
+        &cache,
+        ctx.offline,
+    )
+    .await
+    .map_err(|err| miette::miette!(err))?;
 
     Ok(())
 }
@@ -38,36 +50,48 @@ pub async fn use_cmd(
     requested_version: String,
     paths: &crate::adapters::path::FsPaths,
     platform: &impl crate::ports::Platform,
+    cache: &impl crate::ports::DownloadCache,
+    offline: bool,
 ) -> anyhow::Result<()> {
     let provider = GitHubReleaseProvider::new(client);
     let downloader = ReqwestDownloader::new(client);
     let archive = LocalArchive;
     let fs = TokioFs;
-    let lock = crate::adapters::lock::FileLock::from_paths(paths).await?;
-    let proxy = crate::adapters::proxy::ProxyFsCopier::new(
+    let lock = crate::adapters::lock::FileLock::new(paths.clone());
+    let proxy = crate::adapters::proxy::ProxyFsLinker::new(
         paths.clone(),
         crate::adapters::env::StdEnv,
         crate::adapters::fs::TokioFs,
-        crate::adapters::process::TokioProcess,
     );
     let used_store = crate::adapters::used_store::UsedFileStore::new(paths.clone());
 
-    let parsed_version = resolve_requested_version(&requested_version, &spec, &provider).await?;
+    let downloads_dir = paths.downloads_dir(Package::from_spec(spec.clone(), platform)?).await?;
+    let installed = installed_versions(&fs, &downloads_dir).await.unwrap_or_default();
+    let parsed_version = resolve_for_install(
+        &requested_version,
+        &spec,
+        &installed,
+        platform,
+        cache,
+        &provider,
+        offline,
+    )
+    .await?;
     let package = Package::with_parsed(spec.clone(), parsed_version.clone(), platform)?;
     let version = parsed_version;
     let is_version_used = match used_store.current(package.clone()).await? {
-        Some(current) => current == version.tag_name,
+        Some(current) => spec.version(&current) == spec.version(&version.tag_name),
         None => false,
     };
 
-    proxy.ensure_proxy(&package.alias()).await?;
+    proxy.ensure_proxies(&package.binary_names()).await?;
 
     if is_version_used {
         info!("{} is already in use.", version.tag_name);
         return Ok(());
     }
 
-    install::install(
+    let installed = install::install_version(
         spec,
         version.tag_name.clone(),
         &provider,
@@ -76,15 +100,17 @@ pub async fn use_cmd(
         &fs,
         platform,
         &lock,
-        &used_store,
         paths,
         &proxy,
+        cache,
+        offline,
     )
     .await?;
+    let tag = installed.version().map_or(version.tag_name, |version| version.tag_name);
 
-    used_store.set_current(package.clone(), &version.tag_name).await?;
+    used_store.set_current(package.clone(), &tag).await?;
 
-    info!("You can now use {}!", version.tag_name);
+    info!("You can now use {tag}!");
 
     Ok(())
 }
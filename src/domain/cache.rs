@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// An archive kept in the download cache, addressed by its sha256 digest.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    pub url: String,
+    pub digest: String,
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+/// What the download cache holds on disk, each archive counted once however
+/// many urls resolved to it.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheUsage {
+    pub archives: usize,
+    pub size: u64,
+}
@@ -0,0 +1,43 @@
+/// Finds the SHA-256 digest for `file_name` in a checksum file.
+///
+/// Accepts the common layouts published next to release assets: a bare
+/// digest (`<asset>.sha256`), `sha256sum` output (`<digest>  <file>`, with an
+/// optional `*` binary marker) and BSD style `SHA256 (<file>) = <digest>`.
+pub fn expected_digest(contents: &str, file_name: &str) -> Option<String> {
+    let mut bare = Vec::new();
+
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(rest) = line.strip_prefix("SHA256 (") {
+            if let Some((name, digest)) = rest.split_once(") = ") {
+                if base_name(name) == file_name && is_sha256(digest) {
+                    return Some(digest.to_ascii_lowercase());
+                }
+            }
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let Some(digest) = parts.next().filter(|digest| is_sha256(digest)) else {
+            continue;
+        };
+
+        match parts.next() {
+            Some(name) if base_name(name.trim_start_matches('*')) == file_name => {
+                return Some(digest.to_ascii_lowercase());
+            }
+            Some(_) => {}
+            None => bare.push(digest.to_ascii_lowercase()),
+        }
+    }
+
+    match bare.as_slice() {
+        [digest] => Some(digest.clone()),
+        _ => None,
+    }
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn base_name(path: &str) -> &str { path.rsplit('/').next().unwrap_or(path) }
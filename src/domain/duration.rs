@@ -0,0 +1,18 @@
+use std::time::Duration;
+
+/// Parses durations like `90s`, `15m`, `12h`, `30d` or `8w`. A bare number is
+/// a number of days.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| format!("invalid duration '{value}'"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit '{unit}', use s, m, h, d or w")),
+    };
+    Ok(Duration::from_secs(amount * seconds))
+}
@@ -4,5 +4,9 @@
 //! versions. Higher layers (CLI, adapters) should depend on these instead of
 //! redefining structures.
 
+pub mod cache;
+pub mod checksum;
+pub mod duration;
 pub mod package;
+pub mod platform;
 pub mod version;
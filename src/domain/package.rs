@@ -1,7 +1,10 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use anyhow::Context;
@@ -9,9 +12,17 @@ use anyhow::Result;
 use config::Config;
 use config::File;
 use config::FileFormat;
+use regex::Regex;
+use semver::VersionReq;
 use serde::Deserialize;
 
+use crate::domain::duration::parse_duration;
+use crate::domain::version::padded_semver;
+use crate::domain::version::parse_version_req;
 use crate::domain::version::ParsedVersion;
+use crate::domain::version::RemoteVersion;
+use crate::domain::version::Version;
+use crate::domain::version::VersionKind;
 use crate::ports::Platform;
 
 const DEFAULT_BASE_URL: &str = "https://github.com";
@@ -28,26 +39,93 @@ pub struct PackageSpec {
     pub download_template: String,
     #[serde(default)]
     pub binary_path_template: String,
+    #[serde(default)]
+    pub checksum_template: Option<String>,
+    #[serde(default)]
+    pub binaries: Vec<String>,
     #[serde(default = "default_base_url")]
     pub base_url: String,
     #[serde(default = "default_api_base_url")]
     pub api_base_url: String,
     pub platform: PlatformMatrix,
     pub ext: ExtMatrix,
+    #[serde(skip)]
+    pub overrides: Vec<AssetOverride>,
+    /// Selects the releases of this package among the repo's tags. Its first
+    /// capture group, or the whole match, is the version the tag stands for.
+    #[serde(skip)]
+    pub tag_pattern: Option<Regex>,
+    /// Named versions such as `nightly` or `lts` that can be installed in
+    /// place of a tag.
+    #[serde(skip)]
+    pub channels: HashMap<String, Channel>,
+}
+
+/// What a channel of a package resolves to.
+#[derive(Debug, Clone)]
+pub enum Channel {
+    /// A tag that upstream moves or rebuilds, such as neovim's `nightly`.
+    Tag(String),
+    /// The newest release in `versions`, prereleases included when
+    /// `prerelease` is set.
+    Newest {
+        versions: Option<VersionReq>,
+        prerelease: bool,
+    },
+}
+
+/// An `[org.package.channels]` entry as written in a packages file.
+#[derive(Debug, Clone, Deserialize)]
+struct RawChannel {
+    pub tag: Option<String>,
+    pub versions: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
 }
 
+/// Asset naming for the releases matching `versions`. The templates replace
+/// the package's own, while `platform` and `ext` are merged over them.
+#[derive(Debug, Clone)]
+pub struct AssetOverride {
+    pub versions: VersionReq,
+    pub download_template: Option<String>,
+    pub binary_path_template: Option<String>,
+    pub platform: Option<PlatformMatrix>,
+    pub ext: Option<ExtMatrix>,
+}
+
+/// An `[[org.package.override]]` block as written in a packages file.
+#[derive(Debug, Clone, Deserialize)]
+struct RawAssetOverride {
+    pub versions: String,
+    pub download_template: Option<String>,
+    pub binary_path_template: Option<String>,
+    pub platform: Option<PlatformMatrix>,
+    pub ext: Option<ExtMatrix>,
+}
+
+/// A package entry as written in a packages file. In files layered over the
+/// embedded registry every field but `id` is optional, since an entry may only
+/// override part of an existing package.
 #[derive(Debug, Clone, Deserialize)]
 struct RawPackageSpec {
     pub id: String,
-    pub alias: String,
-    pub repo: String,
-    pub download_template: String,
-    #[serde(default)]
-    pub binary_path_template: String,
+    pub alias: Option<String>,
+    pub repo: Option<String>,
+    pub download_template: Option<String>,
+    pub binary_path_template: Option<String>,
+    pub checksum_template: Option<String>,
+    pub binaries: Option<Vec<String>>,
     pub base_url: Option<String>,
     pub api_base_url: Option<String>,
     pub platform: Option<PlatformMatrix>,
     pub ext: Option<ExtMatrix>,
+    pub tag_pattern: Option<String>,
+    #[serde(rename = "override")]
+    pub overrides: Option<Vec<RawAssetOverride>>,
+    pub channels: Option<HashMap<String, RawChannel>>,
+    #[serde(default)]
+    pub disabled: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -65,6 +143,26 @@ pub struct PlatformMatrix {
     pub windows: Option<ArchMatrix>,
 }
 
+impl PlatformMatrix {
+    /// The OS and arch pairs that have a platform tag.
+    pub fn targets(&self) -> Vec<(&'static str, &'static str)> {
+        [
+            ("macos", &self.macos),
+            ("linux", &self.linux),
+            ("windows", &self.windows),
+        ]
+        .into_iter()
+        .filter_map(|(os, arches)| Some((os, arches.as_ref()?)))
+        .flat_map(|(os, arches)| {
+            [("aarch64", &arches.aarch64), ("x86_64", &arches.x86_64)]
+                .into_iter()
+                .filter(|(_, tag)| tag.is_some())
+                .map(move |(arch, _)| (os, arch))
+        })
+        .collect()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ArchMatrix {
     pub aarch64: Option<String>,
@@ -83,17 +181,212 @@ impl PackageSpec {
         format!("{}/{}/releases/latest", self.api_base_url, self.repo)
     }
 
+    /// Executables exposed through proxies. Defaults to the alias alone.
+    pub fn binary_names(&self) -> Vec<String> {
+        if self.binaries.is_empty() {
+            vec![self.alias.clone()]
+        } else {
+            self.binaries.clone()
+        }
+    }
+
+    /// The GitHub owner of `repo`.
+    pub fn org(&self) -> &str { self.repo.split('/').next().unwrap_or_default() }
+
     pub fn releases_url(&self) -> String { format!("{}/{}/releases", self.api_base_url, self.repo) }
 
+    /// Whether `tag` is rebuilt in place upstream: the tag of a channel, or a
+    /// channel-like tag such as `nightly`.
+    pub fn is_moving_tag(&self, tag: &str) -> bool {
+        self.channels
+            .values()
+            .any(|channel| matches!(channel, Channel::Tag(moving) if moving == tag))
+            || matches!(self.version(tag).kind(), VersionKind::Channel(_))
+    }
+
+    /// Whether `tag` is a release of this package according to `tag_pattern`.
+    pub fn owns_tag(&self, tag: &str) -> bool {
+        self.tag_pattern.as_ref().is_none_or(|pattern| pattern.is_match(tag))
+    }
+
+    /// The version `tag` stands for: what `tag_pattern` extracts from it,
+    /// padded to semver, or the tag itself when there is no pattern or it
+    /// does not match.
+    pub fn version(&self, tag: &str) -> Version {
+        let extracted = self.tag_pattern.as_ref().and_then(|pattern| {
+            let captures = pattern.captures(tag)?;
+            let version = captures.get(1).or_else(|| captures.get(0))?;
+            padded_semver(version.as_str())
+        });
+        match extracted {
+            Some(semver) => Version::with_semver(tag, semver),
+            None => Version::parse(tag),
+        }
+    }
+
+    /// Picks the newest stable release whose version satisfies `req`, or
+    /// any version when there is none.
+    pub fn newest_release(
+        &self,
+        releases: Vec<RemoteVersion>,
+        req: Option<&VersionReq>,
+    ) -> Option<ParsedVersion> {
+        self.newest_matching(releases, req, false)
+    }
+
+    /// Like [`Self::newest_release`], with prereleases taking part when
+    /// `prerelease` is set. A prerelease is then matched against `req` as the
+    /// release it precedes, so `1.2.0-rc.1` is within `~1.2`.
+    pub fn newest_matching(
+        &self,
+        releases: Vec<RemoteVersion>,
+        req: Option<&VersionReq>,
+        prerelease: bool,
+    ) -> Option<ParsedVersion> {
+        let newest = releases
+            .into_iter()
+            .filter(|release| {
+                (prerelease || !release.prerelease) && self.owns_tag(&release.tag_name)
+            })
+            .map(|release| self.version(&release.tag_name))
+            .filter(|version| match req {
+                Some(req) => version.semver().is_some_and(|semver| match prerelease {
+                    true => req.matches(&semver::Version::new(
+                        semver.major,
+                        semver.minor,
+                        semver.patch,
+                    )),
+                    false => req.matches(semver),
+                }),
+                None => true,
+            })
+            .max()?;
+
+        Some(ParsedVersion {
+            tag_name: newest.tag().to_string(),
+            non_parsed_string: newest.tag().to_string(),
+            semver: newest.semver().cloned(),
+        })
+    }
+
+    /// The spec with the first override matching `version` applied. Tags that
+    /// are not semver, like `nightly`, never match an override.
+    pub fn for_version(&self, version: &ParsedVersion) -> Cow<'_, PackageSpec> {
+        let tag_version = self.version(&version.tag_name);
+        let semver = version.semver.as_ref().or(tag_version.semver());
+        let matching = semver
+            .and_then(|semver| self.overrides.iter().find(|entry| entry.versions.matches(semver)));
+        match matching {
+            Some(entry) => Cow::Owned(self.with_override(entry)),
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    /// The spec as it is for the releases `entry` covers.
+    pub fn with_override(&self, entry: &AssetOverride) -> PackageSpec {
+        let mut spec = self.clone();
+        spec.overrides = Vec::new();
+        if let Some(template) = &entry.download_template {
+            spec.download_template = template.clone();
+        }
+        if let Some(template) = &entry.binary_path_template {
+            spec.binary_path_template = template.clone();
+        }
+        if let Some(platform) = merge_platform(entry.platform.clone(), Some(self.platform.clone()))
+        {
+            spec.platform = platform;
+        }
+        if let Some(ext) = merge_ext(entry.ext.clone(), Some(self.ext.clone())) {
+            spec.ext = ext;
+        }
+        spec
+    }
+
     pub fn download_url(
         &self,
         version: &ParsedVersion,
         platform: &impl Platform,
+    ) -> Result<String> {
+        let spec = self.for_version(version);
+        spec.render(&spec.download_template, version, platform)
+    }
+
+    /// The tag whose download url is `url`, found by matching it against the
+    /// download templates of the package and its overrides.
+    pub fn tag_for_url(&self, url: &str, platform: &impl Platform) -> Option<String> {
+        let specs = std::iter::once(self.clone())
+            .chain(self.overrides.iter().map(|entry| self.with_override(entry)));
+        for spec in specs {
+            let (Ok(platform_tag), Ok(file_type)) =
+                (spec.platform_tag(platform), spec.file_type(platform))
+            else {
+                continue;
+            };
+            let pieces: Vec<String> = spec
+                .download_template
+                .split("{version}")
+                .map(|piece| {
+                    regex::escape(
+                        &piece
+                            .replace("{base}", &spec.base_url)
+                            .replace("{repo}", &spec.repo)
+                            .replace("{OS}", platform.os())
+                            .replace("{platform}", &platform_tag)
+                            .replace("{file_type}", &file_type),
+                    )
+                })
+                .collect();
+            let Ok(pattern) = Regex::new(&format!("^{}$", pieces.join("([^/]+)"))) else {
+                continue;
+            };
+            let Some(captures) = pattern.captures(url) else {
+                continue;
+            };
+            let Some(tag) = captures.get(1).map(|tag| tag.as_str().to_string()) else {
+                continue;
+            };
+
+            // Other overrides may claim the tag, so it counts only when it
+            // renders back to `url`.
+            let version = ParsedVersion {
+                tag_name: tag.clone(),
+                non_parsed_string: tag.clone(),
+                semver: None,
+            };
+            if self.download_url(&version, platform).is_ok_and(|rendered| rendered == url) {
+                return Some(tag);
+            }
+        }
+
+        None
+    }
+
+    /// Renders `checksum_template`, if any. Besides the download placeholders
+    /// it accepts `{file}`, the file name of the release asset.
+    pub fn checksum_url(
+        &self,
+        version: &ParsedVersion,
+        platform: &impl Platform,
+    ) -> Result<Option<String>> {
+        let Some(template) = self.checksum_template.as_ref() else {
+            return Ok(None);
+        };
+
+        let download_url = self.download_url(version, platform)?;
+        let file = download_url.rsplit('/').next().unwrap_or_default();
+        let rendered = self.for_version(version).render(template, version, platform)?;
+        Ok(Some(rendered.replace("{file}", file)))
+    }
+
+    fn render(
+        &self,
+        template: &str,
+        version: &ParsedVersion,
+        platform: &impl Platform,
     ) -> Result<String> {
         let platform_tag = self.platform_tag(platform)?;
         let file_type = self.file_type(platform)?;
-        Ok(self
-            .download_template
+        Ok(template
             .replace("{base}", &self.base_url)
             .replace("{repo}", &self.repo)
             .replace("{version}", version.non_parsed_string.as_str())
@@ -114,7 +407,7 @@ impl PackageSpec {
             .replace("{platform}", &platform_tag))
     }
 
-    fn platform_tag(&self, platform: &impl Platform) -> Result<String> {
+    pub fn platform_tag(&self, platform: &impl Platform) -> Result<String> {
         let os = platform.os();
         let arch = platform.arch();
         let arch_map = match os {
@@ -157,6 +450,73 @@ struct PackagesFile {
     package: Vec<RawPackageSpec>,
     #[serde(default)]
     org: Vec<OrgBlock>,
+    #[serde(default)]
+    source: Vec<RegistrySource>,
+}
+
+const DEFAULT_SOURCE_TTL: &str = "1d";
+
+fn default_source_path() -> String { "packages.toml".to_string() }
+
+/// A registry file kept elsewhere, listed as `[[source]]` in a packages file.
+/// It is fetched from `url`, or read at `path` in the `git` repository, and
+/// layered right after the file that lists it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistrySource {
+    pub url: Option<String>,
+    pub git: Option<String>,
+    #[serde(default = "default_source_path")]
+    pub path: String,
+    pub rev: Option<String>,
+    pub ttl: Option<String>,
+}
+
+impl RegistrySource {
+    /// Identifies the source in messages and in the cache.
+    pub fn location(&self) -> String {
+        match (&self.url, &self.git) {
+            (Some(url), _) => url.clone(),
+            (None, Some(git)) => {
+                let rev = self.rev.as_deref().unwrap_or("HEAD");
+                format!("{git}#{rev}:{}", self.path)
+            }
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Rejects values git would read as options and a `path` that leaves the
+    /// repository.
+    fn check(&self, layer: &str) -> Result<()> {
+        let fields = [
+            ("git", self.git.as_deref()),
+            ("rev", self.rev.as_deref()),
+            ("path", Some(self.path.as_str())),
+        ];
+        for (field, value) in fields {
+            if value.is_some_and(|value| value.starts_with('-')) {
+                return Err(anyhow!(
+                    "Registry source in {layer} has a '{field}' starting with '-'"
+                ));
+            }
+        }
+
+        let path = Path::new(&self.path);
+        if path.as_os_str().is_empty()
+            || !path.components().all(|part| matches!(part, Component::Normal(_)))
+        {
+            return Err(anyhow!(
+                "Registry source in {layer} has a 'path' outside its repository: {}",
+                self.path
+            ));
+        }
+        Ok(())
+    }
+
+    /// How long a fetched copy is used before it is refreshed.
+    pub fn ttl(&self) -> Result<Duration> {
+        parse_duration(self.ttl.as_deref().unwrap_or(DEFAULT_SOURCE_TTL))
+            .map_err(|err| anyhow!("Invalid ttl for registry source {}: {err}", self.location()))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -171,66 +531,150 @@ struct OrgBlock {
 struct PackageEntry {
     spec: RawPackageSpec,
     defaults: PackageDefaults,
-    org: Option<String>,
+    source: String,
+}
+
+/// One packages file in the stack the registry is built from.
+pub struct RegistryLayer {
+    /// Where the layer came from, used in error messages.
+    pub source: String,
+    pub contents: String,
+    /// Whether the layer is a project's `hyper-jump.toml`. Such files come
+    /// with whatever repository is checked out, so the sources they list are
+    /// only fetched by `hj registry update`.
+    pub project: bool,
+}
+
+impl RegistryLayer {
+    /// The remote registries this layer lists.
+    pub fn sources(&self) -> Result<Vec<RegistrySource>> {
+        let config = Config::builder()
+            .add_source(File::from_str(&self.contents, FileFormat::Toml))
+            .build()?;
+        let parsed: PackagesFile = config
+            .try_deserialize()
+            .with_context(|| format!("Failed to load packages from {}", self.source))?;
+
+        for source in &parsed.source {
+            if source.url.is_some() == source.git.is_some() {
+                return Err(anyhow!(
+                    "Registry source in {} needs exactly one of 'url' or 'git'",
+                    self.source
+                ));
+            }
+            source.check(&self.source)?;
+        }
+        Ok(parsed.source)
+    }
+
+    pub fn embedded(contents: &str) -> Self {
+        Self {
+            source: "embedded packages".to_string(),
+            contents: contents.to_string(),
+            project: false,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read packages from {}", path.display()))?;
+        Ok(Self {
+            source: path.display().to_string(),
+            contents,
+            project: false,
+        })
+    }
 }
 
 #[derive(Clone)]
 pub struct PackageRegistry {
     by_id: HashMap<String, Arc<PackageSpec>>,
     by_alias: HashMap<String, Arc<PackageSpec>>,
+    by_binary: HashMap<String, Arc<PackageSpec>>,
 }
 
 impl PackageRegistry {
-    pub fn load_from_paths(
+    /// Returns the embedded registry followed, in order, by
+    /// `<root>/packages.toml`, the explicit packages file and the project
+    /// `hyper-jump.toml`, skipping the ones that do not exist.
+    pub fn layers_from_paths(
         explicit_path: Option<PathBuf>,
         default_path: PathBuf,
+        project_path: Option<PathBuf>,
         fallback: &str,
-    ) -> Result<Self> {
+    ) -> Result<Vec<RegistryLayer>> {
+        let mut layers = vec![RegistryLayer::embedded(fallback)];
+        if default_path.exists() {
+            layers.push(RegistryLayer::read(&default_path)?);
+        }
         if let Some(path) = explicit_path {
-            return Self::load_from_path(&path)
-                .with_context(|| format!("Failed to load packages from {}", path.display()));
+            layers.push(RegistryLayer::read(&path)?);
         }
-
-        if default_path.exists() {
-            return Self::load_from_path(&default_path).with_context(|| {
-                format!("Failed to load packages from {}", default_path.display())
+        if let Some(path) = project_path {
+            layers.push(RegistryLayer {
+                project: true,
+                ..RegistryLayer::read(&path)?
             });
         }
 
-        Self::load_from_str(fallback).context("Failed to load embedded packages")
+        Ok(layers)
     }
 
     pub fn load_from_path(path: &Path) -> Result<Self> {
-        let contents = std::fs::read_to_string(path)?;
-        Self::load_from_str(&contents)
+        Self::load_layers(vec![RegistryLayer::read(path)?])
     }
 
     pub fn load_from_str(contents: &str) -> Result<Self> {
-        let config = Config::builder()
-            .add_source(File::from_str(contents, FileFormat::Toml))
-            .build()?;
-        let parsed: PackagesFile = config.try_deserialize()?;
-        let mut entries = Vec::new();
+        Self::load_layers(vec![RegistryLayer {
+            source: "packages.toml".to_string(),
+            contents: contents.to_string(),
+            project: false,
+        }])
+    }
 
-        for spec in parsed.package {
-            entries.push(PackageEntry {
-                spec,
-                defaults: parsed.defaults.clone(),
-                org: None,
-            });
-        }
+    /// Builds the registry from `layers`, each overlaying the previous ones by
+    /// package id: new ids are added, known ids get the fields the layer sets
+    /// and `disabled = true` drops a package. Defaults carry over to later
+    /// layers as well.
+    pub fn load_layers(layers: Vec<RegistryLayer>) -> Result<Self> {
+        let mut entries: Vec<PackageEntry> = Vec::new();
+        let mut defaults = PackageDefaults::default();
 
-        for org in parsed.org {
-            if org.name.trim().is_empty() {
-                return Err(anyhow!("Org name cannot be empty"));
-            }
-            let defaults = merge_defaults(&parsed.defaults, &org.defaults);
-            for spec in org.package {
-                entries.push(PackageEntry {
-                    spec,
-                    defaults: defaults.clone(),
-                    org: Some(org.name.clone()),
-                });
+        for layer in layers {
+            let parsed = parse_layer(&layer, &defaults)
+                .with_context(|| format!("Failed to load packages from {}", layer.source))?;
+            defaults = merge_defaults(&defaults, &parsed.defaults);
+
+            let mut seen = Vec::new();
+            for entry in parsed.entries {
+                if seen.contains(&entry.spec.id) {
+                    return Err(anyhow!(
+                        "Duplicate package id '{}' in {}",
+                        entry.spec.id,
+                        layer.source
+                    ));
+                }
+                seen.push(entry.spec.id.clone());
+
+                let existing = entries.iter().position(|e| e.spec.id == entry.spec.id);
+                match (existing, entry.spec.disabled) {
+                    (Some(index), true) => {
+                        entries.remove(index);
+                    }
+                    (None, true) => {}
+                    (Some(index), false) => {
+                        let base = entries.remove(index);
+                        entries.insert(
+                            index,
+                            PackageEntry {
+                                spec: entry.spec.overlay(base.spec),
+                                defaults: base.defaults,
+                                source: format!("{} (overridden by {})", base.source, entry.source),
+                            },
+                        );
+                    }
+                    (None, false) => entries.push(entry),
+                }
             }
         }
 
@@ -244,6 +688,9 @@ impl PackageRegistry {
         if let Some(spec) = self.by_alias.get(name) {
             return Ok(spec.clone());
         }
+        if let Some(spec) = self.by_binary.get(name) {
+            return Ok(spec.clone());
+        }
 
         Err(anyhow!(
             "Unknown package '{name}'. Available: {}",
@@ -251,11 +698,12 @@ impl PackageRegistry {
         ))
     }
 
-    pub fn get_by_alias(&self, alias: &str) -> Result<Arc<PackageSpec>> {
-        self.by_alias
-            .get(alias)
+    /// Looks up the package that ships the executable `binary`.
+    pub fn get_by_binary(&self, binary: &str) -> Result<Arc<PackageSpec>> {
+        self.by_binary
+            .get(binary)
             .cloned()
-            .ok_or_else(|| anyhow!("Unknown package alias '{alias}'"))
+            .ok_or_else(|| anyhow!("Unknown package binary '{binary}'"))
     }
 
     pub fn ids(&self) -> Vec<String> {
@@ -264,80 +712,272 @@ impl PackageRegistry {
         ids
     }
 
+    /// All packages, ordered by id.
+    pub fn specs(&self) -> Vec<Arc<PackageSpec>> {
+        let mut specs: Vec<Arc<PackageSpec>> = self.by_id.values().cloned().collect();
+        specs.sort_by(|a, b| a.id.cmp(&b.id));
+        specs
+    }
+
     fn from_entries(entries: Vec<PackageEntry>) -> Result<Self> {
         let mut by_id = HashMap::new();
         let mut by_alias = HashMap::new();
+        let mut sources: HashMap<String, String> = HashMap::new();
 
         for entry in entries {
-            let spec = entry.spec;
-            if spec.id.trim().is_empty() {
-                return Err(anyhow!("Package id cannot be empty"));
-            }
-
-            if spec.alias.trim().is_empty() {
-                return Err(anyhow!("Package alias cannot be empty"));
-            }
-
-            if spec.repo.trim().is_empty() {
-                return Err(anyhow!("Package repo cannot be empty"));
-            }
-
-            let spec = Arc::new(spec.into_spec(&entry.defaults, entry.org.as_deref())?);
+            let source = entry.source.clone();
+            let spec = Arc::new(entry.into_spec()?);
+            let describe = |id: &str| format!("'{id}' from {}", sources[id]);
             if by_id.insert(spec.id.clone(), spec.clone()).is_some() {
-                return Err(anyhow!("Duplicate package id '{}'", spec.id));
+                return Err(anyhow!("Duplicate package id '{}' in {source}", spec.id));
             }
 
             if let Some(existing) = by_id.get(&spec.alias) {
                 if !Arc::ptr_eq(existing, &spec) {
                     return Err(anyhow!(
-                        "Alias '{}' conflicts with package id '{}'",
+                        "Alias '{}' of package '{}' from {source} conflicts with package {}",
                         spec.alias,
-                        existing.id
+                        spec.id,
+                        describe(&existing.id)
                     ));
                 }
             }
 
-            if by_alias.insert(spec.alias.clone(), spec.clone()).is_some() {
-                return Err(anyhow!("Duplicate package alias '{}'", spec.alias));
+            if let Some(existing) = by_alias.insert(spec.alias.clone(), spec.clone()) {
+                return Err(anyhow!(
+                    "Alias '{}' of package '{}' from {source} is also used by package {}",
+                    spec.alias,
+                    spec.id,
+                    describe(&existing.id)
+                ));
+            }
+            sources.insert(spec.id.clone(), source);
+        }
+
+        let mut by_binary: HashMap<String, Arc<PackageSpec>> = HashMap::new();
+        for spec in by_id.values() {
+            for binary in spec.binary_names() {
+                let owner = by_binary
+                    .get(&binary)
+                    .or_else(|| by_id.get(&binary))
+                    .or_else(|| by_alias.get(&binary))
+                    .filter(|owner| !Arc::ptr_eq(owner, spec));
+                if let Some(owner) = owner {
+                    return Err(anyhow!(
+                        "Binary '{binary}' of package '{}' from {} conflicts with package '{}' \
+                         from {}",
+                        spec.id,
+                        sources[&spec.id],
+                        owner.id,
+                        sources[&owner.id]
+                    ));
+                }
+                by_binary.insert(binary, spec.clone());
             }
         }
 
-        Ok(Self { by_id, by_alias })
+        Ok(Self {
+            by_id,
+            by_alias,
+            by_binary,
+        })
     }
 }
 
+struct ParsedLayer {
+    defaults: PackageDefaults,
+    entries: Vec<PackageEntry>,
+}
+
+/// Parses one layer, resolving its defaults on top of `inherited`.
+fn parse_layer(layer: &RegistryLayer, inherited: &PackageDefaults) -> Result<ParsedLayer> {
+    let config = Config::builder()
+        .add_source(File::from_str(&layer.contents, FileFormat::Toml))
+        .build()?;
+    let parsed: PackagesFile = config.try_deserialize()?;
+    let defaults = merge_defaults(inherited, &parsed.defaults);
+    let mut entries = Vec::new();
+
+    for spec in parsed.package {
+        entries.push(PackageEntry {
+            spec,
+            defaults: defaults.clone(),
+            source: layer.source.clone(),
+        });
+    }
+
+    for org in parsed.org {
+        if org.name.trim().is_empty() {
+            return Err(anyhow!("Org name cannot be empty"));
+        }
+        let defaults = merge_defaults(&defaults, &org.defaults);
+        for mut spec in org.package {
+            spec.repo = spec.repo.map(|repo| match repo.contains('/') {
+                true => repo,
+                false => format!("{}/{repo}", org.name),
+            });
+            entries.push(PackageEntry {
+                spec,
+                defaults: defaults.clone(),
+                source: layer.source.clone(),
+            });
+        }
+    }
+
+    Ok(ParsedLayer { defaults, entries })
+}
+
 impl RawPackageSpec {
-    fn into_spec(self, defaults: &PackageDefaults, org: Option<&str>) -> Result<PackageSpec> {
-        let base_url = self
+    /// Applies the fields set in `self` over `base`.
+    fn overlay(self, base: RawPackageSpec) -> RawPackageSpec {
+        RawPackageSpec {
+            id: base.id,
+            alias: self.alias.or(base.alias),
+            repo: self.repo.or(base.repo),
+            download_template: self.download_template.or(base.download_template),
+            binary_path_template: self.binary_path_template.or(base.binary_path_template),
+            checksum_template: self.checksum_template.or(base.checksum_template),
+            binaries: self.binaries.or(base.binaries),
+            base_url: self.base_url.or(base.base_url),
+            api_base_url: self.api_base_url.or(base.api_base_url),
+            platform: merge_platform(self.platform, base.platform),
+            ext: merge_ext(self.ext, base.ext),
+            tag_pattern: self.tag_pattern.or(base.tag_pattern),
+            overrides: self.overrides.or(base.overrides),
+            channels: match (self.channels, base.channels) {
+                (Some(channels), Some(mut base)) => {
+                    base.extend(channels);
+                    Some(base)
+                }
+                (channels, base) => channels.or(base),
+            },
+            disabled: false,
+        }
+    }
+}
+
+impl PackageEntry {
+    fn into_spec(self) -> Result<PackageSpec> {
+        let PackageEntry {
+            spec,
+            defaults,
+            source,
+        } = self;
+        let id = spec.id;
+        if id.trim().is_empty() {
+            return Err(anyhow!("Package id cannot be empty in {source}"));
+        }
+        let required = |value: Option<String>, field: &str| {
+            value
+                .filter(|value| !value.trim().is_empty())
+                .ok_or_else(|| anyhow!("Package '{id}' from {source} is missing '{field}'"))
+        };
+
+        let alias = required(spec.alias, "alias")?;
+        let repo = required(spec.repo, "repo")?;
+        let download_template = required(spec.download_template, "download_template")?;
+        let base_url = spec
             .base_url
             .or_else(|| defaults.base_url.clone())
             .unwrap_or_else(default_base_url);
-        let api_base_url = self
+        let api_base_url = spec
             .api_base_url
             .or_else(|| defaults.api_base_url.clone())
             .unwrap_or_else(default_api_base_url);
-        let platform = merge_platform(self.platform, defaults.platform.clone())
-            .ok_or_else(|| anyhow!("Package '{}' missing platform mapping", self.id))?;
-        let ext = merge_ext(self.ext, defaults.ext.clone())
-            .ok_or_else(|| anyhow!("Package '{}' missing file type mapping", self.id))?;
-        let repo = if self.repo.contains('/') {
-            self.repo
-        } else if let Some(org) = org {
-            format!("{org}/{}", self.repo)
-        } else {
-            self.repo
-        };
+        let platform = merge_platform(spec.platform, defaults.platform.clone())
+            .ok_or_else(|| anyhow!("Package '{id}' from {source} missing platform mapping"))?;
+        let ext = merge_ext(spec.ext, defaults.ext.clone())
+            .ok_or_else(|| anyhow!("Package '{id}' from {source} missing file type mapping"))?;
+        let overrides = spec
+            .overrides
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                let versions = parse_version_req(&entry.versions)
+                    .or_else(|| VersionReq::parse(&entry.versions).ok())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Package '{id}' from {source} has an override for invalid versions \
+                             '{}'",
+                            entry.versions
+                        )
+                    })?;
+                Ok(AssetOverride {
+                    versions,
+                    download_template: entry.download_template,
+                    binary_path_template: entry.binary_path_template,
+                    platform: entry.platform,
+                    ext: entry.ext,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let tag_pattern = spec
+            .tag_pattern
+            .map(|pattern| Regex::new(&pattern))
+            .transpose()
+            .map_err(|err| {
+                anyhow!("Package '{id}' from {source} has an invalid tag_pattern: {err}")
+            })?;
+
+        let channels = spec
+            .channels
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, channel)| {
+                let channel = match channel {
+                    RawChannel {
+                        tag: Some(tag),
+                        versions: None,
+                        prerelease: false,
+                    } => Channel::Tag(tag),
+                    RawChannel {
+                        tag: None,
+                        versions,
+                        prerelease,
+                    } if versions.is_some() || prerelease => {
+                        let versions = versions
+                            .map(|versions| {
+                                parse_version_req(&versions)
+                                    .or_else(|| VersionReq::parse(&versions).ok())
+                                    .ok_or_else(|| {
+                                        anyhow!(
+                                            "Package '{id}' from {source} has channel '{name}' \
+                                             with invalid versions '{versions}'"
+                                        )
+                                    })
+                            })
+                            .transpose()?;
+                        Channel::Newest {
+                            versions,
+                            prerelease,
+                        }
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "Package '{id}' from {source} has channel '{name}' that must set \
+                             either a 'tag' or 'versions' and 'prerelease'"
+                        ))
+                    }
+                };
+                Ok((name, channel))
+            })
+            .collect::<Result<_>>()?;
 
         Ok(PackageSpec {
-            id: self.id,
-            alias: self.alias,
+            id,
+            alias,
             repo,
-            download_template: self.download_template,
-            binary_path_template: self.binary_path_template,
+            download_template,
+            binary_path_template: spec.binary_path_template.unwrap_or_default(),
+            checksum_template: spec.checksum_template,
+            binaries: spec.binaries.unwrap_or_default(),
             base_url,
             api_base_url,
             platform,
             ext,
+            overrides,
+            tag_pattern,
+            channels,
         })
     }
 }
@@ -400,6 +1040,7 @@ pub struct Package {
     spec: Arc<PackageSpec>,
     version: Option<ParsedVersion>,
     binary_path: String,
+    binary: Option<String>,
 }
 
 impl Package {
@@ -409,6 +1050,7 @@ impl Package {
             spec,
             version: None,
             binary_path,
+            binary: None,
         })
     }
 
@@ -417,17 +1059,447 @@ impl Package {
         version: ParsedVersion,
         platform: &impl Platform,
     ) -> Result<Self> {
-        let binary_path = spec.binary_path(platform)?;
+        let binary_path = spec.for_version(&version).binary_path(platform)?;
         Ok(Self {
             spec,
             version: Some(version),
             binary_path,
+            binary: None,
         })
     }
 
+    /// The same package at `version`, keeping the selected binary.
+    pub fn at_version(&self, version: ParsedVersion, platform: &impl Platform) -> Result<Self> {
+        let package = Self::with_parsed(self.spec.clone(), version, platform)?;
+        Ok(Self {
+            binary: self.binary.clone(),
+            ..package
+        })
+    }
+
+    /// Selects which of the package executables `binary_name` refers to.
+    pub fn with_binary(mut self, binary: &str) -> Self {
+        self.binary = Some(binary.to_string());
+        self
+    }
+
     pub fn spec(&self) -> &PackageSpec { &self.spec }
     pub fn alias(&self) -> String { self.spec.alias.clone() }
     pub fn version(&self) -> Option<ParsedVersion> { self.version.clone() }
     pub fn binary_path(&self) -> String { self.binary_path.clone() }
-    pub fn binary_name(&self) -> String { self.spec.alias.clone() }
+    pub fn binary_name(&self) -> String {
+        self.binary.clone().unwrap_or_else(|| self.spec.alias.clone())
+    }
+    pub fn binary_names(&self) -> Vec<String> { self.spec.binary_names() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::platform::TargetPlatform;
+
+    const BASE: &str = r#"
+        [defaults.platform.linux]
+        x86_64 = "x86_64-unknown-linux-gnu"
+
+        [defaults.ext]
+        linux = "tar.gz"
+
+        [[package]]
+        id = "tool"
+        alias = "tl"
+        repo = "acme/tool"
+        download_template = "{base}/{repo}/releases/download/{version}/tool-{platform}.{file_type}"
+        binary_path_template = "bin"
+
+        [package.channels]
+        nightly = { tag = "nightly" }
+
+        [[package]]
+        id = "other"
+        alias = "ot"
+        repo = "acme/other"
+        download_template = "{base}/{repo}/releases/download/{version}/other.{file_type}"
+    "#;
+
+    fn layer(source: &str, contents: &str) -> RegistryLayer {
+        RegistryLayer {
+            source: source.to_string(),
+            contents: contents.to_string(),
+            project: false,
+        }
+    }
+
+    fn load(overlay: &str) -> Result<PackageRegistry> {
+        PackageRegistry::load_layers(vec![layer("base", BASE), layer("user", overlay)])
+    }
+
+    /// The error loading `overlay` fails with.
+    fn load_err(overlay: &str) -> String {
+        match load(overlay) {
+            Ok(_) => panic!("expected the registry to be rejected"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn overlay_keeps_the_fields_it_does_not_set() {
+        let registry = load(
+            r#"
+            [[package]]
+            id = "tool"
+            repo = "fork/tool"
+
+            [package.channels]
+            stable = { tag = "stable" }
+            "#,
+        )
+        .unwrap();
+
+        let spec = registry.resolve("tl").unwrap();
+        assert_eq!(spec.repo, "fork/tool");
+        assert_eq!(spec.alias, "tl");
+        assert_eq!(spec.binary_path_template, "bin");
+        assert!(spec.download_template.ends_with("tool-{platform}.{file_type}"));
+        assert!(spec.channels.contains_key("nightly"));
+        assert!(spec.channels.contains_key("stable"));
+    }
+
+    #[test]
+    fn overlay_adds_new_packages_with_inherited_defaults() {
+        let registry = load(
+            r#"
+            [[package]]
+            id = "extra"
+            alias = "ex"
+            repo = "acme/extra"
+            download_template = "{base}/{repo}/releases/download/{version}/extra.{file_type}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(registry.ids(), ["extra", "other", "tool"]);
+        let platform = TargetPlatform {
+            os: "linux",
+            arch: "x86_64",
+        };
+        let spec = registry.resolve("extra").unwrap();
+        assert_eq!(spec.file_type(&platform).unwrap(), "tar.gz");
+    }
+
+    #[test]
+    fn disabled_drops_a_package_and_ignores_unknown_ids() {
+        let registry = load(
+            r#"
+            [[package]]
+            id = "other"
+            disabled = true
+
+            [[package]]
+            id = "missing"
+            disabled = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(registry.ids(), ["tool"]);
+        assert!(registry.resolve("ot").is_err());
+    }
+
+    #[test]
+    fn duplicate_ids_within_a_layer_are_rejected() {
+        let err = load_err(
+            r#"
+            [[package]]
+            id = "tool"
+            repo = "fork/tool"
+
+            [[package]]
+            id = "tool"
+            repo = "other/tool"
+            "#,
+        );
+
+        assert_eq!(err, "Duplicate package id 'tool' in user");
+    }
+
+    #[test]
+    fn alias_conflicts_name_both_sources() {
+        let err = load_err(
+            r#"
+            [[package]]
+            id = "other"
+            alias = "tl"
+            "#,
+        );
+
+        assert_eq!(
+            err,
+            "Alias 'tl' of package 'other' from base (overridden by user) is also used by package \
+             'tool' from base"
+        );
+
+        let err = load_err(
+            r#"
+            [[package]]
+            id = "extra"
+            alias = "tool"
+            repo = "acme/extra"
+            download_template = "{base}/{repo}/releases/download/{version}/extra.{file_type}"
+            "#,
+        );
+
+        assert_eq!(
+            err,
+            "Alias 'tool' of package 'extra' from user conflicts with package 'tool' from base"
+        );
+    }
+
+    #[test]
+    fn binary_conflicts_name_both_sources() {
+        let err = load_err(
+            r#"
+            [[package]]
+            id = "other"
+            binaries = ["ot", "tl"]
+            "#,
+        );
+
+        assert!(err.starts_with("Binary 'tl' of package"));
+        assert!(err.contains("from base (overridden by user)"));
+    }
+
+    fn parsed(tag: &str) -> ParsedVersion {
+        ParsedVersion {
+            tag_name: tag.to_string(),
+            non_parsed_string: tag.to_string(),
+            semver: Version::parse(tag).semver().cloned(),
+        }
+    }
+
+    fn embedded(id: &str) -> Arc<PackageSpec> {
+        PackageRegistry::load_from_str(include_str!("../../packages.toml"))
+            .unwrap()
+            .resolve(id)
+            .unwrap()
+    }
+
+    /// The asset name and binary path of `tag` for `os` and `arch`.
+    fn asset(
+        spec: &PackageSpec,
+        tag: &str,
+        os: &'static str,
+        arch: &'static str,
+    ) -> (String, String) {
+        let platform = TargetPlatform { os, arch };
+        let version = parsed(tag);
+        let url = spec.download_url(&version, &platform).unwrap();
+        let binary_path = spec.for_version(&version).binary_path(&platform).unwrap();
+        (url.rsplit('/').next().unwrap().to_string(), binary_path)
+    }
+
+    #[test]
+    fn neovim_overrides_follow_the_asset_renames() {
+        let nvim = embedded("neovim");
+        let linux = |tag| asset(&nvim, tag, "linux", "x86_64");
+        let macos = |tag| asset(&nvim, tag, "macos", "aarch64");
+
+        assert_eq!(
+            linux("v0.9.5"),
+            ("nvim-linux64.tar.gz".into(), "nvim-linux64/bin".into())
+        );
+        assert_eq!(
+            linux("v0.10.3"),
+            ("nvim-linux64.tar.gz".into(), "nvim-linux64/bin".into())
+        );
+        assert_eq!(
+            linux("v0.10.4"),
+            (
+                "nvim-linux-x86_64.tar.gz".into(),
+                "nvim-linux-x86_64/bin".into()
+            )
+        );
+
+        assert_eq!(macos("v0.9.5").1, "nvim-macos/bin");
+        assert_eq!(macos("v0.10.0").1, "nvim-macos-arm64/bin");
+        assert_eq!(macos("v0.10.4").1, "nvim-macos-arm64/bin");
+    }
+
+    #[test]
+    fn tags_without_a_version_use_the_package_fields() {
+        let nvim = embedded("neovim");
+        assert_eq!(
+            asset(&nvim, "nightly", "linux", "x86_64"),
+            (
+                "nvim-linux-x86_64.tar.gz".into(),
+                "nvim-linux-x86_64/bin".into()
+            )
+        );
+        assert!(matches!(
+            nvim.for_version(&parsed("stable")),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn first_matching_override_wins() {
+        let registry = load(
+            r#"
+            [[package]]
+            id = "tool"
+
+            [[package.override]]
+            versions = "<2"
+            binary_path_template = "old"
+
+            [[package.override]]
+            versions = "<3"
+            binary_path_template = "older"
+            "#,
+        )
+        .unwrap();
+        let spec = registry.resolve("tool").unwrap();
+
+        assert_eq!(
+            spec.for_version(&parsed("v1.4.0")).binary_path_template,
+            "old"
+        );
+        assert_eq!(
+            spec.for_version(&parsed("2.0.0")).binary_path_template,
+            "older"
+        );
+        assert_eq!(
+            spec.for_version(&parsed("v3.0.0")).binary_path_template,
+            "bin"
+        );
+    }
+
+    #[test]
+    fn overrides_can_change_the_archive_format() {
+        let registry = load(
+            r#"
+            [[package]]
+            id = "tool"
+
+            [[package.override]]
+            versions = "<2"
+
+            [package.override.ext]
+            linux = "zip"
+            "#,
+        )
+        .unwrap();
+        let spec = registry.resolve("tool").unwrap();
+        let platform = TargetPlatform {
+            os: "linux",
+            arch: "x86_64",
+        };
+
+        let old = parsed("v1.0.0");
+        assert_eq!(spec.for_version(&old).file_type(&platform).unwrap(), "zip");
+        assert!(spec.download_url(&old, &platform).unwrap().ends_with(".zip"));
+
+        let new = parsed("v2.0.0");
+        assert_eq!(
+            spec.for_version(&new).file_type(&platform).unwrap(),
+            "tar.gz"
+        );
+        assert!(spec.download_url(&new, &platform).unwrap().ends_with(".tar.gz"));
+    }
+
+    #[test]
+    fn download_urls_map_back_to_their_tag() {
+        let nvim = embedded("neovim");
+        let linux = TargetPlatform {
+            os: "linux",
+            arch: "x86_64",
+        };
+        for tag in ["v0.9.5", "v0.10.3", "v0.10.4", "nightly"] {
+            let url = nvim.download_url(&parsed(tag), &linux).unwrap();
+            assert_eq!(nvim.tag_for_url(&url, &linux).as_deref(), Some(tag));
+        }
+
+        let url = nvim.download_url(&parsed("v0.9.5"), &linux).unwrap();
+        let macos = TargetPlatform {
+            os: "macos",
+            arch: "aarch64",
+        };
+        assert_eq!(nvim.tag_for_url(&url, &macos), None);
+        assert_eq!(
+            nvim.tag_for_url("https://example.com/nvim.tar.gz", &linux),
+            None
+        );
+    }
+
+    fn release(tag: &str, prerelease: bool) -> RemoteVersion {
+        RemoteVersion {
+            name: tag.to_string(),
+            tag_name: tag.to_string(),
+            prerelease,
+            published_at: None,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tag_pattern_extracts_mithril_versions() {
+        let mithril = embedded("mithril");
+
+        let version = mithril.version("2450.0");
+        assert_eq!(version.tag(), "2450.0");
+        assert_eq!(
+            version.semver(),
+            semver::Version::parse("2450.0.0").ok().as_ref()
+        );
+        assert!(mithril.version("2450.0") > mithril.version("2445.1"));
+        assert_eq!(mithril.version("unstable"), Version::parse("unstable"));
+
+        assert!(mithril.owns_tag("2450.0"));
+        assert!(!mithril.owns_tag("2450.0-pre"));
+        assert!(!mithril.owns_tag("unstable"));
+    }
+
+    #[test]
+    fn tag_pattern_picks_the_partner_chains_node_tags() {
+        let node = embedded("partner-chains-node");
+
+        let version = node.version("partner-chains-node-v1.5.0");
+        assert_eq!(version.tag(), "partner-chains-node-v1.5.0");
+        assert_eq!(
+            version.semver(),
+            semver::Version::parse("1.5.0").ok().as_ref()
+        );
+        assert!(node.owns_tag("partner-chains-node-v1.5.0"));
+        assert!(!node.owns_tag("partner-chains-cli-v1.5.0"));
+        assert!(!node.owns_tag("partner-chains-node-v1.5.0-rc1"));
+
+        let releases = vec![
+            release("partner-chains-cli-v1.6.0", false),
+            release("partner-chains-node-v1.4.1", false),
+            release("partner-chains-node-v1.5.0", false),
+            release("partner-chains-node-v1.10.0-rc1", false),
+        ];
+        let newest = node.newest_release(releases, None).unwrap();
+        assert_eq!(newest.tag_name, "partner-chains-node-v1.5.0");
+    }
+
+    #[test]
+    fn releases_outside_the_tag_pattern_are_skipped() {
+        let mithril = embedded("mithril");
+        let releases = || {
+            vec![
+                release("2445.1", false),
+                release("2450.0", false),
+                release("2451.0-pre", false),
+                release("unstable", false),
+            ]
+        };
+
+        let newest = mithril.newest_release(releases(), None).unwrap();
+        assert_eq!(newest.tag_name, "2450.0");
+
+        let req = parse_version_req("2445").unwrap();
+        let newest = mithril.newest_release(releases(), Some(&req)).unwrap();
+        assert_eq!(newest.tag_name, "2445.1");
+    }
 }
@@ -0,0 +1,14 @@
+use crate::ports::Platform;
+
+/// A fixed OS and arch, used to render specs for machines other than this one.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetPlatform {
+    pub os: &'static str,
+    pub arch: &'static str,
+}
+
+impl Platform for TargetPlatform {
+    fn os(&self) -> &'static str { self.os }
+
+    fn arch(&self) -> &'static str { self.arch }
+}
@@ -1,6 +1,12 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::PathBuf;
+
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
 use regex::Regex;
-use semver::Version;
+use semver::VersionReq;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -10,7 +16,7 @@ pub struct LocalVersion {
     pub file_name: String,
     pub file_format: String,
     pub path: String,
-    pub semver: Option<Version>,
+    pub semver: Option<semver::Version>,
 }
 
 /// Represents a remote version retrieved from GitHub.
@@ -19,6 +25,44 @@ pub struct RemoteVersion {
     pub name: String,
     pub tag_name: String,
     pub prerelease: bool,
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// A file attached to a remote release.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ReleaseAsset {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Identifies the build of a moving tag such as `nightly` that was
+/// installed, so a rebuild under the same tag can be told apart.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ReleaseStamp {
+    pub asset_id: Option<u64>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ReleaseStamp {
+    /// The stamp of the asset named `file_name` in `release`, or of the
+    /// release itself when it has no such asset.
+    pub fn of(release: &RemoteVersion, file_name: &str) -> Self {
+        match release.assets.iter().find(|asset| asset.name == file_name) {
+            Some(asset) => Self {
+                asset_id: Some(asset.id),
+                updated_at: asset.updated_at,
+            },
+            None => Self {
+                asset_id: None,
+                updated_at: release.published_at,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,38 +72,354 @@ pub enum VersionStatus {
     NotInstalled,
 }
 
+/// A version pinned for a package by a project file.
+#[derive(Debug, Clone)]
+pub struct PinnedVersion {
+    pub version: String,
+    pub file: PathBuf,
+}
+
 /// Represents a parsed version of the software.
 #[derive(Debug, Clone)]
 pub struct ParsedVersion {
     pub tag_name: String,
     pub non_parsed_string: String,
-    pub semver: Option<Version>,
+    pub semver: Option<semver::Version>,
 }
 
 pub async fn parse_normal_version(version: &str) -> Result<ParsedVersion> {
-    let semver = semver(version)?;
-    let returned_version = match (semver, version.starts_with('v')) {
-        (true, false) => parse_semver(version)?,
-        _ => ParsedVersion {
-            tag_name: version.to_string(),
-            non_parsed_string: version.to_string(),
-            semver: None,
-        },
-    };
-
-    Ok(returned_version)
+    Ok(ParsedVersion {
+        tag_name: version.to_string(),
+        non_parsed_string: version.to_string(),
+        semver: Version::parse(version).semver().cloned(),
+    })
 }
 
 pub fn semver(version: &str) -> Result<bool> {
     Ok(Regex::new(r"^v?[0-9]+\.[0-9]+\.[0-9]+$")?.is_match(version))
 }
 
-fn parse_semver(version: &str) -> Result<ParsedVersion> {
-    let version = version.to_string();
-    let semver = Version::parse(&version)?;
-    Ok(ParsedVersion {
-        tag_name: version.clone(),
-        non_parsed_string: version.clone(),
-        semver: Some(semver),
-    })
+/// Channel tags, from the least to the most stable.
+const CHANNELS: [&str; 9] = [
+    "nightly", "canary", "edge", "dev", "unstable", "preview", "beta", "stable", "lts",
+];
+
+/// What a release tag says about the release it names.
+#[derive(Debug, Clone)]
+pub enum VersionKind {
+    /// `1.2.3`, `v1.2.3-rc.1`, `1.2.3+build.5`.
+    Semver(semver::Version),
+    /// Year-led tags such as `2024.01.15`, `2024-01-15`, `20240115` or
+    /// `2024.1`, as their numeric components.
+    Calendar(Vec<u64>),
+    /// Moving tags such as `stable` or `nightly`.
+    Channel(String),
+    Other,
+}
+
+/// A release tag along with its parsed meaning.
+///
+/// Versions are totally ordered: semver and calendar versions by their
+/// numbers, above channels, which are above any other tag. Two versions are
+/// equal when they mean the same release, so `v1.2.3` equals `1.2.3`.
+#[derive(Debug, Clone)]
+pub struct Version {
+    tag: String,
+    kind: VersionKind,
+}
+
+impl Version {
+    pub fn parse(tag: &str) -> Self {
+        let trimmed = tag.trim();
+        let unprefixed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+        let kind = if let Ok(semver) = semver::Version::parse(unprefixed) {
+            VersionKind::Semver(semver)
+        } else if let Some(numbers) = calendar(unprefixed) {
+            VersionKind::Calendar(numbers)
+        } else if CHANNELS.contains(&trimmed.to_ascii_lowercase().as_str()) {
+            VersionKind::Channel(trimmed.to_ascii_lowercase())
+        } else {
+            VersionKind::Other
+        };
+
+        Self {
+            tag: tag.to_string(),
+            kind,
+        }
+    }
+
+    /// A tag whose version was worked out elsewhere, e.g. by a tag pattern.
+    pub fn with_semver(tag: &str, semver: semver::Version) -> Self {
+        Self {
+            tag: tag.to_string(),
+            kind: VersionKind::Semver(semver),
+        }
+    }
+
+    /// The tag exactly as it was given.
+    pub fn tag(&self) -> &str { &self.tag }
+
+    pub fn kind(&self) -> &VersionKind { &self.kind }
+
+    pub fn semver(&self) -> Option<&semver::Version> {
+        match &self.kind {
+            VersionKind::Semver(semver) => Some(semver),
+            _ => None,
+        }
+    }
+
+    /// Whether the tag carries version numbers, so that being greater means
+    /// being newer. Channels and other tags only compare for equality in a
+    /// meaningful way.
+    pub fn is_numbered(&self) -> bool {
+        matches!(self.kind, VersionKind::Semver(_) | VersionKind::Calendar(_))
+    }
+
+    /// Whether this is `other` or a newer release. Tags without numbers are
+    /// only that when they are the same tag.
+    pub fn is_at_least(&self, other: &Version) -> bool {
+        match self.is_numbered() && other.is_numbered() {
+            true => self >= other,
+            false => self == other,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self.kind {
+            VersionKind::Other => 0,
+            VersionKind::Channel(_) => 1,
+            VersionKind::Calendar(_) => 2,
+            VersionKind::Semver(_) => 3,
+        }
+    }
+
+    fn numbers(&self) -> Vec<u64> {
+        match &self.kind {
+            VersionKind::Semver(semver) => vec![semver.major, semver.minor, semver.patch],
+            VersionKind::Calendar(numbers) => numbers.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.kind, &other.kind) {
+            (VersionKind::Semver(a), VersionKind::Semver(b)) => a.cmp(b),
+            // Mixed semver and calendar tags go by their numbers, then kind.
+            _ if self.is_numbered() && other.is_numbered() => {
+                self.numbers().cmp(&other.numbers()).then(self.rank().cmp(&other.rank()))
+            }
+            (VersionKind::Channel(a), VersionKind::Channel(b)) => {
+                let position = |name: &str| CHANNELS.iter().position(|channel| *channel == name);
+                position(a).cmp(&position(b))
+            }
+            _ => self.rank().cmp(&other.rank()).then_with(|| self.tag.cmp(&other.tag)),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.tag) }
+}
+
+/// Reads year-led tags: `YYYY.MM[.DD][.N]`, the same with dashes, or
+/// `YYYYMMDD`.
+fn calendar(tag: &str) -> Option<Vec<u64>> {
+    let numbers: Vec<u64> = match tag.len() == 8 && tag.chars().all(|c| c.is_ascii_digit()) {
+        true => vec![
+            tag[..4].parse().ok()?,
+            tag[4..6].parse().ok()?,
+            tag[6..].parse().ok()?,
+        ],
+        false => tag.split(['.', '-']).map(|part| part.parse().ok()).collect::<Option<_>>()?,
+    };
+
+    let year = (1970..=2999).contains(numbers.first()?);
+    let month = (1..=12).contains(numbers.get(1)?);
+    let day = numbers.get(2).is_none_or(|day| (1..=31).contains(day));
+    (year && month && day && numbers.len() <= 4).then_some(numbers)
+}
+
+/// Parses a version requirement such as `^1.10`, `~0.11.2`, `1.*`,
+/// `>=1.2, <2` or a partial version like `0.11`.
+///
+/// Returns `None` for anything that should be treated as a literal tag,
+/// including fully specified versions like `v1.10.2`. Partial versions match
+/// every patch of the given minor (or every minor of the given major), and a
+/// `v` prefix is accepted anywhere a version appears.
+pub fn parse_version_req(requested: &str) -> Option<VersionReq> {
+    let requested = requested.trim();
+    if semver(requested).ok()? {
+        return None;
+    }
+
+    let partial = Regex::new(r"^v?[0-9]+(\.[0-9]+)?$").ok()?;
+    if partial.is_match(requested) {
+        let exact = format!("={}", requested.trim_start_matches('v'));
+        return VersionReq::parse(&exact).ok();
+    }
+
+    let range = Regex::new(r"^[\^~<>=*xXv0-9., ]+$").ok()?;
+    if !range.is_match(requested) {
+        return None;
+    }
+
+    let prefix = Regex::new(r"(^|[\s,^~<>=])v([0-9])").ok()?;
+    let normalized = prefix.replace_all(requested, "$1$2");
+    VersionReq::parse(&normalized).ok()
+}
+
+/// Parses a version that may leave out its minor or patch number, like
+/// `2450.0`, filling them in with zeros. A leading `v` is ignored.
+pub fn padded_semver(version: &str) -> Option<semver::Version> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let (core, rest) = version.split_at(version.find(['-', '+']).unwrap_or(version.len()));
+    let padded = match core.split('.').count() {
+        1 => format!("{core}.0.0{rest}"),
+        2 => format!("{core}.0{rest}"),
+        _ => version.to_string(),
+    };
+    semver::Version::parse(&padded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(tags: &[&str]) -> Vec<String> {
+        let mut versions: Vec<Version> = tags.iter().map(|tag| Version::parse(tag)).collect();
+        versions.sort();
+        versions.iter().map(|version| version.tag().to_string()).collect()
+    }
+
+    #[test]
+    fn prefix_does_not_change_the_version() {
+        assert_eq!(Version::parse("v1.2.3"), Version::parse("1.2.3"));
+        assert_eq!(Version::parse("v1.2.3").tag(), "v1.2.3");
+        assert_ne!(Version::parse("nightly"), Version::parse("stable"));
+    }
+
+    #[test]
+    fn semver_orders_prereleases_and_build_metadata() {
+        assert_eq!(
+            sorted(&[
+                "v1.10.0",
+                "v1.2.0",
+                "1.10.0-rc.1",
+                "1.10.0+build.2",
+                "1.10.0-alpha"
+            ]),
+            [
+                "v1.2.0",
+                "1.10.0-alpha",
+                "1.10.0-rc.1",
+                "v1.10.0",
+                "1.10.0+build.2"
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_tags_order_by_date() {
+        assert_eq!(
+            sorted(&["2024.10.01", "20240115", "2023-12-31", "2024.02"]),
+            ["2023-12-31", "20240115", "2024.02", "2024.10.01"]
+        );
+        assert_eq!(Version::parse("2024-01-15"), Version::parse("20240115"));
+    }
+
+    #[test]
+    fn numbered_versions_rank_above_channels_and_other_tags() {
+        assert_eq!(
+            sorted(&["stable", "v0.10.4", "release-candidate", "nightly"]),
+            ["release-candidate", "nightly", "stable", "v0.10.4"]
+        );
+    }
+
+    #[test]
+    fn mixed_semver_and_calendar_tags_order_by_their_numbers() {
+        assert_eq!(
+            sorted(&["2024.02.01", "v2024.1.15", "2024.01.15"]),
+            ["2024.01.15", "v2024.1.15", "2024.02.01"]
+        );
+    }
+
+    #[test]
+    fn padded_semver_fills_in_missing_components() {
+        assert_eq!(
+            padded_semver("2450.0"),
+            semver::Version::parse("2450.0.0").ok()
+        );
+        assert_eq!(padded_semver("v3"), semver::Version::parse("3.0.0").ok());
+        assert_eq!(
+            padded_semver("1.2-rc.1"),
+            semver::Version::parse("1.2.0-rc.1").ok()
+        );
+    }
+
+    #[test]
+    fn exact_versions_are_not_ranges() {
+        assert_eq!(parse_version_req("1.2.3"), None);
+        assert_eq!(parse_version_req("v1.2.3"), None);
+        assert_eq!(parse_version_req("nightly"), None);
+        assert_eq!(parse_version_req("latest"), None);
+    }
+
+    #[test]
+    fn partial_versions_match_their_whole_series() {
+        let req = parse_version_req("v1.2").unwrap();
+        assert!(req.matches(&semver::Version::new(1, 2, 9)));
+        assert!(!req.matches(&semver::Version::new(1, 3, 0)));
+
+        let req = parse_version_req("1").unwrap();
+        assert!(req.matches(&semver::Version::new(1, 9, 0)));
+        assert!(!req.matches(&semver::Version::new(2, 0, 0)));
+    }
+
+    #[test]
+    fn ranges_accept_a_v_prefix() {
+        assert_eq!(parse_version_req("~v1.2"), VersionReq::parse("~1.2").ok());
+        assert_eq!(
+            parse_version_req(">=v1.0, <v2"),
+            VersionReq::parse(">=1.0, <2").ok()
+        );
+        assert_eq!(parse_version_req("^0.10"), VersionReq::parse("^0.10").ok());
+        assert_eq!(parse_version_req("1.x"), VersionReq::parse("1.x").ok());
+        assert_eq!(parse_version_req("1.2.3-rc.1"), None);
+    }
+
+    #[test]
+    fn release_stamp_follows_the_downloaded_asset() {
+        let release: RemoteVersion = serde_json::from_str(
+            r#"{
+                "name": "Nightly",
+                "tag_name": "nightly",
+                "prerelease": true,
+                "published_at": "2026-10-15T00:00:00Z",
+                "assets": [
+                    {"id": 7, "name": "nvim-linux-x86_64.tar.gz", "updated_at": "2026-10-15T03:00:00Z"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let stamp = ReleaseStamp::of(&release, "nvim-linux-x86_64.tar.gz");
+        assert_eq!(stamp.asset_id, Some(7));
+        assert_eq!(stamp.updated_at, "2026-10-15T03:00:00Z".parse().ok());
+
+        let stamp = ReleaseStamp::of(&release, "nvim-macos-arm64.tar.gz");
+        assert_eq!(stamp.asset_id, None);
+        assert_eq!(stamp.updated_at, release.published_at);
+    }
 }
@@ -5,21 +5,34 @@ mod domain;
 mod ports;
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use adapters::client;
 use adapters::env::StdEnv;
+use adapters::pin_store::find_project_file;
+use adapters::registry_source::CachedRegistrySources;
 use clap::Parser;
 use clap::Subcommand;
 use clap::ValueEnum;
+use commands::cache;
 use commands::erase;
+use commands::exec;
+use commands::info;
 use commands::install;
 use commands::list;
 use commands::list_remote;
+use commands::outdated;
+use commands::pin;
 use commands::prefix;
+use commands::prune;
+use commands::registry;
+use commands::reshim;
 use commands::uninstall;
+use commands::upgrade;
 use commands::use_cmd;
 use domain::package::PackageRegistry;
-use tracing::Level;
+use domain::package::RegistryLayer;
+use ports::SourceRefresh;
 use tracing_indicatif::IndicatifLayer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -49,6 +62,32 @@ struct Cli {
         env = "HYPER_JUMP_OUTPUT_FORMAT"
     )]
     output_format: Option<OutputFormat>,
+
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 30,
+        help = "seconds to wait for a connection",
+        env = "HYPER_JUMP_CONNECT_TIMEOUT"
+    )]
+    connect_timeout: u64,
+
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 60,
+        help = "seconds to wait for data on an open connection",
+        env = "HYPER_JUMP_READ_TIMEOUT"
+    )]
+    read_timeout: u64,
+
+    #[arg(
+        long,
+        global = true,
+        help = "install only from the download cache, without network access",
+        env = "HYPER_JUMP_OFFLINE"
+    )]
+    offline: bool,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -62,28 +101,66 @@ enum Commands {
     Use(use_cmd::Args),
     List(list::Args),
     Install(install::Args),
+    Exec(exec::Args),
     Uninstall(uninstall::Args),
+    Upgrade(upgrade::Args),
     ListRemote(list_remote::Args),
+    Pin(pin::Args),
+    Outdated,
+    Info(info::Args),
+    Prune(prune::Args),
+    Cache(cache::Args),
+    Registry(registry::Args),
     Prefix,
+    Reshim,
     Erase,
 }
 
+impl Commands {
+    /// Whether the command looks up releases, and so should see the registry
+    /// sources refreshed. The others read what is installed and make do with
+    /// the cached sources; `hj registry update` fetches them on demand.
+    fn needs_remote(&self) -> bool {
+        matches!(
+            self,
+            Commands::Use(_)
+                | Commands::Install(_)
+                | Commands::Exec(_)
+                | Commands::Upgrade(_)
+                | Commands::ListRemote(_)
+                | Commands::Pin(_)
+                | Commands::Info(_)
+                | Commands::Outdated
+        )
+    }
+}
+
 pub struct Context {
     pub dirs: adapters::dirs::Dirs,
     pub output_format: OutputFormat,
     pub packages: PackageRegistry,
+    pub offline: bool,
 }
 
 impl Context {
-    fn for_cli(cli: &Cli, env: &dyn crate::ports::Env) -> miette::Result<Self> {
+    async fn for_cli(
+        cli: &Cli,
+        env: &dyn crate::ports::Env,
+        client: Option<&reqwest::Client>,
+    ) -> miette::Result<Self> {
         let dirs = adapters::dirs::Dirs::try_new(cli.root_dir.as_deref(), env)?;
         let output_format = cli.output_format.clone().unwrap_or(OutputFormat::Table);
-        let packages = load_registry(env, &dirs)?;
+        let refresh = match !cli.offline && cli.command.needs_remote() {
+            true => SourceRefresh::IfStale,
+            false => SourceRefresh::CacheOnly,
+        };
+        let packages = load_registry(env, &dirs, client, refresh).await?;
 
         Ok(Context {
             dirs,
             output_format,
             packages,
+            offline: cli.offline,
         })
     }
 }
@@ -92,25 +169,43 @@ pub fn with_tracing() {
     let indicatif_layer = IndicatifLayer::new();
     tracing_subscriber::registry()
         .with(tracing_subscriber::filter::LevelFilter::INFO)
-        .with(tracing_subscriber::filter::Targets::default().with_target("hyper-jump", Level::INFO))
-        .with(tracing_subscriber::fmt::layer().with_writer(indicatif_layer.get_stderr_writer()))
+        .with(tracing_subscriber::fmt::layer().with_writer(indicatif_layer.get_stdout_writer()))
         .with(indicatif_layer)
         .init();
 }
 
-fn load_registry(
+pub fn registry_layers(
     env: &dyn crate::ports::Env,
     dirs: &adapters::dirs::Dirs,
-) -> miette::Result<PackageRegistry> {
+) -> miette::Result<Vec<RegistryLayer>> {
     let explicit = env.packages_file();
     let default_path = dirs.root_dir.join("packages.toml");
-    PackageRegistry::load_from_paths(explicit, default_path, include_str!("../packages.toml"))
+    let project_path = env.current_dir().ok().and_then(|dir| find_project_file(&dir));
+    PackageRegistry::layers_from_paths(
+        explicit,
+        default_path,
+        project_path,
+        include_str!("../packages.toml"),
+    )
+    .map_err(|e| miette::miette!(e))
+}
+
+async fn load_registry(
+    env: &dyn crate::ports::Env,
+    dirs: &adapters::dirs::Dirs,
+    client: Option<&reqwest::Client>,
+    refresh: SourceRefresh,
+) -> miette::Result<PackageRegistry> {
+    let layers = registry_layers(env, dirs)?;
+    let sources = CachedRegistrySources::new(dirs.registry_dir(), client);
+    app::registry::load_registry(layers, &sources, refresh)
+        .await
         .map_err(|e| miette::miette!(e))
 }
 
 #[tokio::main]
 async fn main() -> miette::Result<()> {
-    tracing_subscriber::fmt::init();
+    with_tracing();
     let env_adapter = StdEnv;
     let env_ref: &dyn crate::ports::Env = &env_adapter;
     let args: Vec<String> = env_ref.args();
@@ -120,10 +215,14 @@ async fn main() -> miette::Result<()> {
     if !exe_name.eq(env!("CARGO_BIN_NAME")) {
         let root_dir = env_ref.root_dir();
         let dirs = adapters::dirs::Dirs::try_new(root_dir.as_deref(), env_ref)?;
-        let registry = load_registry(env_ref, &dirs)?;
+        let registry = load_registry(env_ref, &dirs, None, SourceRefresh::CacheOnly).await?;
         let paths = adapters::path::FsPaths::new(dirs.root_dir.clone());
         let used_store = adapters::used_store::UsedFileStore::new(paths.clone());
         let platform = adapters::platform::StdPlatform;
+        let pins = adapters::pin_store::ProjectPinStore::new(
+            env_ref.current_dir().map_err(|e| miette::miette!(e))?,
+        );
+        let fs = adapters::fs::TokioFs;
         let process = adapters::process::TokioProcess;
         let output = adapters::output::StdoutOutput;
         return app::proxy::handle_proxy(
@@ -133,6 +232,8 @@ async fn main() -> miette::Result<()> {
             &output,
             &paths,
             &used_store,
+            &pins,
+            &fs,
             &platform,
             &process,
         )
@@ -140,16 +241,34 @@ async fn main() -> miette::Result<()> {
     }
 
     let cli = Cli::parse();
-    let ctx = Context::for_cli(&cli, env_ref)?;
-    let client = Some(client::create_reqwest_client().map_err(|e| miette::miette!(e))?);
+    let client = match cli.offline {
+        true => None,
+        false => Some(
+            client::create_reqwest_client(
+                Duration::from_secs(cli.connect_timeout),
+                Duration::from_secs(cli.read_timeout),
+            )
+            .map_err(|e| miette::miette!(e))?,
+        ),
+    };
+    let ctx = Context::for_cli(&cli, env_ref, client.as_ref()).await?;
 
     match cli.command {
         Commands::Use(args) => use_cmd::run(args, &ctx, client.as_ref()).await,
         Commands::List(args) => list::run(args, &ctx, client.as_ref()).await,
         Commands::Install(args) => install::run(args, &ctx, client.as_ref()).await,
-        Commands::Uninstall(args) => uninstall::run(args, &ctx, client.as_ref()).await,
+        Commands::Exec(args) => exec::run(args, &ctx, client.as_ref()).await,
+        Commands::Uninstall(args) => uninstall::run(args, &ctx).await,
+        Commands::Upgrade(args) => upgrade::run(args, &ctx, client.as_ref()).await,
         Commands::ListRemote(args) => list_remote::run(args, &ctx, client.as_ref()).await,
+        Commands::Pin(args) => pin::run(args, &ctx, client.as_ref()).await,
+        Commands::Info(args) => info::run(args, &ctx, client.as_ref()).await,
+        Commands::Outdated => outdated::run(&ctx, client.as_ref()).await,
+        Commands::Prune(args) => prune::run(args, &ctx).await,
+        Commands::Cache(args) => cache::run(args, &ctx).await,
+        Commands::Registry(args) => registry::run(args, &ctx, client.as_ref()).await,
         Commands::Prefix => prefix::run(&ctx).await,
+        Commands::Reshim => reshim::run(&ctx).await,
         Commands::Erase => erase::run(&ctx).await,
     }
 }
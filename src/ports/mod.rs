@@ -7,20 +7,61 @@
 use std::fs::File;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
+use crate::domain::cache::CacheEntry;
+use crate::domain::cache::CacheUsage;
 use crate::domain::package::Package;
 use crate::domain::package::PackageSpec;
+use crate::domain::package::RegistryLayer;
+use crate::domain::package::RegistrySource;
 use crate::domain::version::LocalVersion;
 use crate::domain::version::ParsedVersion;
+use crate::domain::version::PinnedVersion;
 use crate::domain::version::RemoteVersion;
 
 pub trait ReleaseProvider: Send + Sync {
     async fn latest(&self, package: &PackageSpec) -> anyhow::Result<ParsedVersion>;
     async fn list(&self, package: &PackageSpec) -> anyhow::Result<Vec<RemoteVersion>>;
+    /// The release tagged `tag`, with its assets.
+    async fn release(&self, package: &PackageSpec, tag: &str) -> anyhow::Result<RemoteVersion>;
 }
 
 pub trait Downloader: Send + Sync {
-    async fn download(&self, url: &str, dest: &Path) -> anyhow::Result<()>;
+    /// Downloads `url` into `dest` and returns the hex SHA-256 of the bytes
+    /// written.
+    async fn download(&self, url: &str, dest: &Path) -> anyhow::Result<String>;
+    async fn fetch(&self, url: &str) -> anyhow::Result<String>;
+    /// Sends a `HEAD` request and reports whether `url` exists.
+    async fn exists(&self, url: &str) -> anyhow::Result<bool>;
+}
+
+pub trait DownloadCache: Send + Sync {
+    /// Returns the cached archive for `url`, provided its digest matches
+    /// `expected` when one is given.
+    async fn get(&self, url: &str, expected: Option<&str>) -> anyhow::Result<Option<CacheEntry>>;
+    async fn put(&self, url: &str, file: &Path, digest: &str) -> anyhow::Result<()>;
+    async fn entries(&self) -> anyhow::Result<Vec<CacheEntry>>;
+    async fn usage(&self) -> anyhow::Result<CacheUsage>;
+    async fn clean(&self) -> anyhow::Result<()>;
+}
+
+/// When a registry source is fetched again instead of read from the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceRefresh {
+    CacheOnly,
+    IfStale,
+    Always,
+}
+
+pub trait RegistrySources: Send + Sync {
+    /// Returns the source's registry file, fetching it as `refresh` asks.
+    /// `None` means it was never fetched and could not be now.
+    async fn load(
+        &self,
+        source: &RegistrySource,
+        refresh: SourceRefresh,
+    ) -> anyhow::Result<Option<RegistryLayer>>;
 }
 
 pub trait Archive: Send + Sync {
@@ -33,14 +74,40 @@ pub trait Fs: Send + Sync {
     async fn read_dir(&self, path: &Path) -> anyhow::Result<Vec<PathBuf>>;
     async fn remove_dir_all(&self, path: &Path) -> anyhow::Result<()>;
     async fn remove_file(&self, path: &Path) -> anyhow::Result<()>;
-    async fn set_current_dir(&self, path: &Path) -> anyhow::Result<()>;
     async fn is_dir(&self, path: &Path) -> anyhow::Result<bool>;
     async fn exists(&self, path: &Path) -> anyhow::Result<bool>;
+    async fn symlink(&self, target: &Path, link: &Path) -> anyhow::Result<()>;
+    async fn hard_link(&self, src: &Path, dest: &Path) -> anyhow::Result<()>;
+    async fn read_link(&self, path: &Path) -> anyhow::Result<Option<PathBuf>>;
+    async fn rename(&self, src: &Path, dest: &Path) -> anyhow::Result<()>;
+    async fn modified(&self, path: &Path) -> anyhow::Result<SystemTime>;
+    async fn read_to_string(&self, path: &Path) -> anyhow::Result<String>;
+    async fn write(&self, path: &Path, contents: &str) -> anyhow::Result<()>;
+    /// Returns the total size of the files under `path`, without following
+    /// symlinks.
+    async fn dir_size(&self, path: &Path) -> anyhow::Result<u64>;
+    /// Describes the file at `path` itself, not what a symlink there points
+    /// at, or `None` when there is none.
+    async fn stamp(&self, path: &Path) -> anyhow::Result<Option<FileStamp>>;
+}
+
+/// Tells files apart without reading them: hard links share the inode, and a
+/// copy has the size of its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub dev: u64,
+    pub ino: u64,
+    pub len: u64,
+    pub modified: SystemTime,
 }
 
 pub trait Paths: Send + Sync {
     async fn downloads_dir(&self, package: Package) -> anyhow::Result<PathBuf>;
     async fn installation_dir(&self) -> anyhow::Result<PathBuf>;
+    /// Where `package` is kept, without creating it.
+    fn package_dir(&self, package: &Package) -> PathBuf;
+    /// Where the proxies are kept, without creating it.
+    fn bin_dir(&self) -> PathBuf;
 }
 
 pub trait RootDir: Send + Sync {
@@ -63,17 +130,19 @@ pub struct LockGuard {
 }
 
 pub trait Lock: Send + Sync {
-    async fn acquire(&self) -> anyhow::Result<LockGuard>;
+    async fn acquire(&self, package: Package) -> anyhow::Result<LockGuard>;
 }
 
 pub trait Env: Send + Sync {
     fn exe_name(&self) -> String;
     fn args(&self) -> Vec<String>;
     fn root_dir(&self) -> Option<PathBuf>;
+    fn cache_dir(&self) -> Option<PathBuf>;
     fn packages_file(&self) -> Option<PathBuf>;
     fn home_dir(&self) -> Option<PathBuf>;
     fn current_exe(&self) -> anyhow::Result<PathBuf>;
     fn path_var(&self) -> Option<String>;
+    fn current_dir(&self) -> anyhow::Result<PathBuf>;
 }
 
 pub trait UsedVersionStore: Send + Sync {
@@ -81,11 +150,26 @@ pub trait UsedVersionStore: Send + Sync {
     async fn set_current(&self, package: Package, version: &str) -> anyhow::Result<()>;
 }
 
+pub trait PinStore: Send + Sync {
+    async fn pinned(&self, package: Package) -> anyhow::Result<Option<PinnedVersion>>;
+    async fn pin(&self, package: Package, version: &str) -> anyhow::Result<PathBuf>;
+}
+
 pub trait Process: Send + Sync {
+    /// Runs `program` in place of the current process where possible, so the
+    /// exit status and signals belong to `program` itself.
     async fn run(&self, program: &Path, args: &[String]) -> anyhow::Result<()>;
-    async fn output(&self, program: &Path, args: &[String]) -> anyhow::Result<Vec<u8>>;
 }
 
 pub trait ProxyInstaller: Send + Sync {
-    async fn ensure_proxy(&self, alias: &str) -> anyhow::Result<()>;
+    async fn ensure_proxy(&self, name: &str) -> anyhow::Result<()>;
+    async fn rebuild_proxy(&self, name: &str) -> anyhow::Result<()>;
+    async fn remove_proxy(&self, name: &str) -> anyhow::Result<()>;
+
+    async fn ensure_proxies(&self, names: &[String]) -> anyhow::Result<()> {
+        for name in names {
+            self.ensure_proxy(name).await?;
+        }
+        Ok(())
+    }
 }